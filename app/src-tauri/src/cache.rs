@@ -0,0 +1,147 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// `.easypaper/cache/` inside the project, where derived data is kept
+pub fn cache_dir(project_dir: &str) -> PathBuf {
+    PathBuf::from(project_dir).join(".easypaper").join("cache")
+}
+
+/// Reads a JSON cache file, returning None when it doesn't exist yet
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.to_string_lossy(), e))
+}
+
+/// Writes a JSON cache file, creating its parent directories
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.to_string_lossy(), e))?;
+
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.to_string_lossy(), e))
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Hex-encoded SHA-256 of `bytes`, used as a stable cache key
+pub fn hash_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Files that can affect the compiled output
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "tex", "bib", "sty", "cls", "bst", "bbx", "cbx", "def", "cfg", "png", "jpg", "jpeg", "pdf",
+    "eps", "svg",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotEntry {
+    pub size: u64,
+    pub modified: u64,
+    pub hash: String,
+}
+
+/// Source files of the project keyed by relative path
+pub type SourceSnapshot = BTreeMap<String, SnapshotEntry>;
+
+fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Whether `path` is a document source: TeX, bibliography, style or image file
+pub fn is_source_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// Hashes every source file outside `out_dir`, `.easypaper` and `.git`.
+/// Files whose size and mtime match `previous` reuse the stored hash.
+pub fn snapshot_sources(project_dir: &str, out_dir: &Path, previous: Option<&SourceSnapshot>) -> SourceSnapshot {
+    // Absolute so entries compare against the (absolute) output directory
+    let root = std::path::absolute(project_dir).unwrap_or_else(|_| PathBuf::from(project_dir));
+    let mut snapshot = SourceSnapshot::new();
+
+    let walker = WalkDir::new(&root).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.path() != out_dir && name != ".easypaper" && name != ".git"
+    });
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if !is_source_file(entry.path()) {
+            continue;
+        }
+
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let rel_path = entry
+            .path()
+            .strip_prefix(&root)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        let size = meta.len();
+        let modified = modified_secs(&meta);
+
+        let unchanged = previous
+            .and_then(|p| p.get(&rel_path))
+            .filter(|old| old.size == size && old.modified == modified);
+        let hash = match unchanged {
+            Some(old) => old.hash.clone(),
+            None => match std::fs::read(entry.path()) {
+                Ok(bytes) => hash_hex(&bytes),
+                Err(_) => continue,
+            },
+        };
+
+        snapshot.insert(rel_path, SnapshotEntry { size, modified, hash });
+    }
+
+    snapshot
+}
+
+/// Paths that were added, modified or removed between two snapshots
+pub fn changed_files(previous: &SourceSnapshot, current: &SourceSnapshot) -> Vec<String> {
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(path, entry)| previous.get(*path).map(|old| old.hash != entry.hash).unwrap_or(true))
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    changed.extend(previous.keys().filter(|path| !current.contains_key(*path)).cloned());
+    changed.sort();
+    changed
+}
@@ -0,0 +1,319 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+/// A document with every \input/\include/\subfile expanded in place
+#[derive(Debug, Clone)]
+pub struct ResolvedDoc {
+    pub text: String,
+    /// Source location of each line of `text`
+    pub lines: Vec<SourceLocation>,
+    /// Every file that was read, main file first (paths relative to the project)
+    pub files: Vec<String>,
+    /// Targets of \include as written (without .tex), including ones \includeonly leaves out
+    pub includes: Vec<String>,
+}
+
+impl ResolvedDoc {
+    /// Iterates the resolved lines together with where they came from
+    pub fn source_lines(&self) -> impl Iterator<Item = (&SourceLocation, &str)> {
+        self.lines.iter().zip(self.text.lines())
+    }
+}
+
+fn include_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\(input|include|subfileinclude|subfile)\{([^}]+)\}").unwrap())
+}
+
+fn includeonly_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\includeonly\s*\{([^}]*)\}").unwrap())
+}
+
+// `\include` targets compare by name, with or without the .tex extension
+fn include_name(target: &str) -> &str {
+    let target = target.trim();
+    target.strip_suffix(".tex").unwrap_or(target)
+}
+
+/// The names listed by `\includeonly{...}` in the preamble, if any
+pub fn include_only(content: &str) -> Option<Vec<String>> {
+    let preamble = extract_preamble(content).unwrap_or(content);
+    preamble
+        .lines()
+        .map(strip_comment)
+        .find_map(|line| includeonly_regex().captures(line))
+        .map(|caps| {
+            caps[1]
+                .split(',')
+                .map(include_name)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+}
+
+fn subfiles_class_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\documentclass\s*\[([^\]]*)\]\s*\{subfiles\}").unwrap())
+}
+
+/// The main file named by `\documentclass[main.tex]{subfiles}`, if this is a subfiles document
+pub fn subfile_parent(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(strip_comment)
+        .find_map(|line| subfiles_class_regex().captures(line))
+        .map(|caps| caps[1].trim().to_string())
+}
+
+/// Recursively expand \input, \include and \subfile starting from `main`.
+/// Like LaTeX, `\include` starts a new page and is skipped when `\includeonly` omits it.
+pub fn resolve_document(project_dir: &Path, main: &str) -> Result<ResolvedDoc, String> {
+    let main = normalize(main);
+    let only = std::fs::read_to_string(project_dir.join(&main))
+        .ok()
+        .and_then(|content| include_only(&content));
+
+    let mut doc = ResolvedDoc {
+        text: String::new(),
+        lines: Vec::new(),
+        files: Vec::new(),
+        includes: Vec::new(),
+    };
+    let mut stack = Vec::new();
+    expand_file(project_dir, &main, false, only.as_deref(), &mut stack, &mut doc)?;
+    Ok(doc)
+}
+
+fn expand_file(
+    project_dir: &Path,
+    rel_path: &str,
+    body_only: bool,
+    only: Option<&[String]>,
+    stack: &mut Vec<String>,
+    doc: &mut ResolvedDoc,
+) -> Result<(), String> {
+    if stack.iter().any(|f| f == rel_path) {
+        return Err(format!(
+            "Include cycle detected: {} -> {}",
+            stack.join(" -> "),
+            rel_path
+        ));
+    }
+
+    let content = std::fs::read_to_string(project_dir.join(rel_path))
+        .map_err(|e| format!("Failed to read {}: {}", rel_path, e))?;
+
+    if !doc.files.iter().any(|f| f == rel_path) {
+        doc.files.push(rel_path.to_string());
+    }
+    stack.push(rel_path.to_string());
+
+    // A subfile contributes only its document body; its preamble is the main file's
+    let lines: Vec<&str> = content.lines().collect();
+    let (first, last) = if body_only { document_body(&lines) } else { (0, lines.len()) };
+
+    for (idx, line) in lines.iter().enumerate().take(last).skip(first) {
+        let location = SourceLocation {
+            file: rel_path.to_string(),
+            line: idx as u32 + 1,
+        };
+
+        // Only commands outside comments are expanded
+        let code = strip_comment(line);
+        let mut cursor = 0;
+        let mut expanded = false;
+
+        for caps in include_regex().captures_iter(code) {
+            let is_include = &caps[1] == "include";
+            let name = include_name(&caps[2]);
+            if is_include && !doc.includes.iter().any(|i| i == name) {
+                doc.includes.push(name.to_string());
+            }
+            let excluded = is_include && only.is_some_and(|only| !only.iter().any(|o| o == name));
+
+            let target = resolve_include(project_dir, rel_path, &caps[2]);
+            if target.is_none() && !excluded {
+                // Leave missing files for the engine to complain about
+                continue;
+            }
+
+            let whole = caps.get(0).unwrap();
+            let before = &line[cursor..whole.start()];
+            if !before.trim().is_empty() {
+                push_line(doc, before, &location);
+            }
+            // \include always breaks the page, even when \includeonly leaves the file out
+            if is_include {
+                push_line(doc, "\\clearpage", &location);
+            }
+            if let (Some(target), false) = (target, excluded) {
+                let is_subfile = caps[1].starts_with("subfile");
+                expand_file(project_dir, &target, is_subfile, only, stack, doc)?;
+                if is_include {
+                    push_line(doc, "\\clearpage", &location);
+                }
+            }
+            cursor = whole.end();
+            expanded = true;
+        }
+
+        if !expanded {
+            push_line(doc, line, &location);
+        } else if !line[cursor..].trim().is_empty() {
+            push_line(doc, &line[cursor..], &location);
+        }
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+// Line range strictly between \begin{document} and \end{document} (whole file if absent)
+fn document_body(lines: &[&str]) -> (usize, usize) {
+    let begin = lines
+        .iter()
+        .position(|l| strip_comment(l).contains("\\begin{document}"));
+    let Some(begin) = begin else {
+        return (0, lines.len());
+    };
+    let end = lines[begin..]
+        .iter()
+        .position(|l| strip_comment(l).contains("\\end{document}"))
+        .map(|pos| begin + pos)
+        .unwrap_or(lines.len());
+    (begin + 1, end)
+}
+
+fn push_line(doc: &mut ResolvedDoc, text: &str, location: &SourceLocation) {
+    doc.text.push_str(text);
+    doc.text.push('\n');
+    doc.lines.push(location.clone());
+}
+
+// Looks next to the including file first, then at the project root like the engines do
+fn resolve_include(project_dir: &Path, including: &str, target: &str) -> Option<String> {
+    let mut target = target.trim().to_string();
+    if Path::new(&target).extension().is_none() {
+        target.push_str(".tex");
+    }
+
+    // Absolute targets are read as written; they may live outside the project
+    if Path::new(&target).is_absolute() {
+        return Path::new(&target).is_file().then_some(target);
+    }
+
+    let relative = Path::new(including)
+        .parent()
+        .map(|dir| dir.join(&target))
+        .unwrap_or_else(|| PathBuf::from(&target));
+
+    [relative, PathBuf::from(&target)]
+        .into_iter()
+        .map(|p| normalize(&p.to_string_lossy()))
+        .find(|p| project_dir.join(p).is_file())
+}
+
+// Turns `./a/../b.tex` style paths into `b.tex` with forward slashes
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else {
+                    parts.push(part);
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Drops everything after an unescaped '%'
+pub fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    for i in 0..bytes.len() {
+        if bytes[i] == b'%' && (i == 0 || bytes[i - 1] != b'\\') {
+            return &line[..i];
+        }
+    }
+    line
+}
+
+/// Everything before \begin{document}, or None if the file has no document body
+pub fn extract_preamble(content: &str) -> Option<&str> {
+    content.find("\\begin{document}").map(|pos| &content[..pos])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn inputs_without_extension_resolve_to_tex_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "main.tex", "\\begin{document}\n\\input{intro}\n\\end{document}\n");
+        write(dir.path(), "intro.tex", "Hello\n");
+
+        let doc = resolve_document(dir.path(), "main.tex").unwrap();
+        assert_eq!(doc.files, vec!["main.tex", "intro.tex"]);
+        assert!(doc.text.contains("Hello"));
+    }
+
+    #[test]
+    fn inputs_resolve_next_to_the_including_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "main.tex", "\\input{chapters/one}\n");
+        write(dir.path(), "chapters/one.tex", "\\input{two}\n");
+        write(dir.path(), "chapters/two.tex", "Nested\n");
+
+        let doc = resolve_document(dir.path(), "main.tex").unwrap();
+        assert_eq!(doc.files, vec!["main.tex", "chapters/one.tex", "chapters/two.tex"]);
+        assert_eq!(doc.lines.last().unwrap().file, "chapters/two.tex");
+    }
+
+    #[test]
+    fn absolute_inputs_are_read_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        write(shared.path(), "macros.tex", "Shared\n");
+        let target = shared.path().join("macros");
+        write(dir.path(), "main.tex", &format!("\\input{{{}}}\n", target.display()));
+
+        let doc = resolve_document(dir.path(), "main.tex").unwrap();
+        assert_eq!(doc.files.len(), 2);
+        assert!(doc.text.contains("Shared"));
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "main.tex", "\\input{a}\n");
+        write(dir.path(), "a.tex", "\\input{b}\n");
+        write(dir.path(), "b.tex", "\\input{a}\n");
+
+        let err = resolve_document(dir.path(), "main.tex").unwrap_err();
+        assert!(err.contains("Include cycle detected"), "{}", err);
+        assert!(err.contains("a.tex -> b.tex -> a.tex"), "{}", err);
+    }
+}
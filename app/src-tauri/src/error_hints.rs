@@ -0,0 +1,96 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+// (pattern, hint) pairs checked in order against a raw error message; the first match wins
+const HINTS: &[(&str, &str)] = &[
+    (
+        r"Undefined control sequence",
+        "A command is misspelled or its package isn't loaded",
+    ),
+    (
+        r"Missing \$ inserted",
+        "A math-only symbol such as ^, _ or \\alpha is used outside math mode; wrap it in $...$",
+    ),
+    (
+        r"Environment \S+ undefined",
+        "The environment name is misspelled or its package isn't loaded",
+    ),
+    (
+        r"File `[^']+' not found",
+        "The file given to \\input, \\include, \\usepackage or \\includegraphics doesn't exist; check the name and path",
+    ),
+    (
+        r"Missing \\begin\{document\}",
+        "Text or a stray character appears in the preamble, before \\begin{document}",
+    ),
+    (
+        r"\\begin\{[^}]*\} on input line \d+ ended by \\end",
+        "Environments must be closed in the order they were opened; check for a missing or misspelled \\end",
+    ),
+    (
+        r"Extra \}, or forgotten",
+        "Braces are unbalanced: there is a } without a matching {, or a $ or \\right is missing",
+    ),
+    (
+        r"Too many \}'s",
+        "There are more closing braces than opening ones",
+    ),
+    (
+        r"Runaway argument|Paragraph ended before .* was complete",
+        "A command argument is missing its closing brace, or contains a blank line",
+    ),
+    (
+        r"Misplaced alignment tab character &",
+        "& only works inside tabular or align environments; write \\& for a literal ampersand",
+    ),
+    (
+        r"There's no line here to end",
+        "\\\\ was used where no line is being set, e.g. after a blank line; remove it or use \\vspace",
+    ),
+    (
+        r"Option clash for package",
+        "The package is loaded twice with different options; pass all options to a single \\usepackage",
+    ),
+    (
+        r"Can be used only in preamble",
+        "This command must come before \\begin{document}",
+    ),
+    (
+        r"Double (superscript|subscript)",
+        "Group consecutive scripts with braces, e.g. x^{a^b} instead of x^a^b",
+    ),
+    (
+        r"Illegal unit of measure",
+        "A length is missing its unit (pt, cm, mm, em, ...)",
+    ),
+    (
+        r"Missing number, treated as zero",
+        "A command expected a number or length but got something else",
+    ),
+    (
+        r"Display math should end with \$\$",
+        "A $$ display is not closed properly; prefer \\[ ... \\]",
+    ),
+    (
+        r"Emergency stop",
+        "LaTeX stopped early; fix the first error reported before this one",
+    ),
+];
+
+fn hint_table() -> &'static Vec<(Regex, &'static str)> {
+    static TABLE: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        HINTS
+            .iter()
+            .map(|(pattern, hint)| (Regex::new(pattern).unwrap(), *hint))
+            .collect()
+    })
+}
+
+/// A plain-language suggestion for a raw LaTeX error message, if one is known
+pub fn hint_for(message: &str) -> Option<String> {
+    hint_table()
+        .iter()
+        .find(|(re, _)| re.is_match(message))
+        .map(|(_, hint)| hint.to_string())
+}
@@ -1,14 +1,53 @@
 // Module declarations
+mod cache;
+mod document;
+mod error_hints;
 mod project;
+mod svc_aux;
+mod svc_bib;
 mod svc_build;
+mod svc_diff;
+mod svc_document;
+mod svc_engine;
 mod svc_file;
+mod svc_math;
+mod svc_pdf;
+mod svc_project;
+mod svc_refactor;
+mod svc_search;
+mod svc_snapshot;
+mod svc_spell;
 mod svc_template;
 mod svc_synctex;
 
-use svc_build::{build_clean, build_compile};
-use svc_file::{create_dir, file_delete, file_exists, file_list, file_read, file_rename, file_write};
+use svc_aux::aux_parse;
+use svc_bib::{bib_find_unused, bib_format, bib_keys, bib_lint};
+use svc_build::{
+    build_changed_files, build_check_refs, build_clean, build_compile, build_compile_async, build_compile_buffer,
+    build_compile_inline, build_diagnostics_by_file, build_history, build_report, build_subfile, watch_compile_start,
+    watch_compile_stop, BuildQueue, CompileWatchers,
+};
+use svc_diff::{file_diff, file_diff_content};
+use svc_document::{
+    document_flatten, figures_check, macros_list, outline_get, project_metadata, project_stats, project_unused,
+};
+use svc_engine::{engine_detect, engine_set, env_report, packages_check};
+use svc_file::{
+    create_dir, file_backup_restore, file_backups_list, file_delete, file_exists, file_inspect, file_list,
+    file_list_stream, file_read, file_read_encoded, file_read_range, file_rename, file_write,
+};
+use svc_math::math_render;
+use svc_pdf::{pdf_diff, pdf_render_page, pdf_watch_start, pdf_watch_stop, PdfWatchers};
+use svc_project::{
+    project_archive, project_close, project_config_effective, project_ensure_gitignore, project_import, project_open,
+    project_trust, session_load, session_save, ProjectState,
+};
+use svc_refactor::refactor_rename_label;
+use svc_search::file_replace;
+use svc_snapshot::{snapshot_create, snapshot_list, snapshot_restore};
+use svc_spell::spell_check;
 use svc_template::{template_apply, template_get_content, template_list};
-use svc_synctex::{synctex_forward, synctex_backward};
+use svc_synctex::{synctex_backward, synctex_backward_rects, synctex_forward};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -16,18 +55,67 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(ProjectState::default())
+        .manage(BuildQueue::default())
+        .manage(PdfWatchers::default())
+        .manage(CompileWatchers::default())
         .invoke_handler(tauri::generate_handler![
             // File operations
             file_read,
+            file_read_encoded,
+            file_read_range,
+            file_inspect,
             file_write,
+            file_backups_list,
+            file_backup_restore,
             file_list,
+            file_list_stream,
             file_delete,
             file_rename,
             file_exists,
             create_dir,
+            file_diff,
+            file_diff_content,
+            file_replace,
             // Build operations
             build_compile,
+            build_compile_async,
+            build_compile_inline,
+            build_compile_buffer,
+            build_subfile,
             build_clean,
+            build_check_refs,
+            build_history,
+            build_changed_files,
+            build_diagnostics_by_file,
+            build_report,
+            watch_compile_start,
+            watch_compile_stop,
+            // Project lifecycle
+            project_open,
+            project_config_effective,
+            project_close,
+            project_ensure_gitignore,
+            project_trust,
+            project_import,
+            project_archive,
+            session_save,
+            session_load,
+            // Snapshots
+            snapshot_create,
+            snapshot_list,
+            snapshot_restore,
+            // Engines
+            engine_detect,
+            engine_set,
+            env_report,
+            packages_check,
+            // PDF preview
+            pdf_watch_start,
+            pdf_watch_stop,
+            pdf_render_page,
+            pdf_diff,
+            math_render,
             // Template operations
             template_list,
             template_apply,
@@ -35,6 +123,25 @@ pub fn run() {
             // SyncTeX operations
             synctex_forward,
             synctex_backward,
+            synctex_backward_rects,
+            // Document analysis
+            figures_check,
+            outline_get,
+            document_flatten,
+            project_stats,
+            project_metadata,
+            project_unused,
+            macros_list,
+            aux_parse,
+            // Refactoring
+            refactor_rename_label,
+            // Bibliography
+            bib_lint,
+            bib_find_unused,
+            bib_keys,
+            bib_format,
+            // Spell checking
+            spell_check,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,5 +1,8 @@
+use crate::svc_engine::executable_in;
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
@@ -8,6 +11,14 @@ pub struct ProjectConfig {
     pub main: String,
     pub engine: EngineConfig,
     pub compile: CompileConfig,
+    /// Shell commands run in the project directory before compiling / after a successful compile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_build: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_build: Option<String>,
+    /// Earlier versions kept per file when saving with `backup`; 0 turns backups off
+    #[serde(default = "default_max_backups")]
+    pub max_backups: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +27,12 @@ pub struct EngineConfig {
     pub engine_type: String, // "tectonic" or "latexmk"
     #[serde(default)]
     pub args: Vec<String>,
+    /// Engines tried in order when this one isn't installed, e.g. ["latexmk"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_engines: Vec<String>,
+    /// TeX distribution to run instead of the one on PATH, e.g. "/usr/local/texlive/2023"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tex_dist_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +45,23 @@ pub struct CompileConfig {
     pub outdir: String,
     #[serde(default = "default_min_interval")]
     pub min_interval_ms: u64,
+    #[serde(default)]
+    pub precompile_preamble: bool,
+    /// Number of engine runs; unset reruns until the .aux file stops changing
+    #[serde(default)]
+    pub passes: Option<u8>,
+    /// Compile in a temporary directory and copy only the PDF, SyncTeX and log into `outdir`
+    #[serde(default)]
+    pub use_temp_builddir: bool,
+    /// Run makeglossaries between passes; unset detects \makeglossaries / \printglossary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glossaries: Option<bool>,
+    /// Index processor for documents with \makeindex / \printindex: "makeindex" (default) or "xindy"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_tool: Option<String>,
+    /// Regexes for build warnings to hide, e.g. "Font shape .* undefined"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppress_warnings: Vec<String>,
 }
 
 fn default_true() -> bool {
@@ -42,6 +76,10 @@ fn default_min_interval() -> u64 {
     600
 }
 
+fn default_max_backups() -> usize {
+    5
+}
+
 impl Default for ProjectConfig {
     fn default() -> Self {
         ProjectConfig {
@@ -51,44 +89,284 @@ impl Default for ProjectConfig {
             engine: EngineConfig {
                 engine_type: "tectonic".to_string(),
                 args: vec![],
+                fallback_engines: vec![],
+                tex_dist_path: None,
             },
             compile: CompileConfig {
                 synctex: true,
                 shell_escape: false,
                 outdir: "out".to_string(),
                 min_interval_ms: 600,
+                precompile_preamble: false,
+                passes: None,
+                use_temp_builddir: false,
+                glossaries: None,
+                index_tool: None,
+                suppress_warnings: vec![],
             },
+            pre_build: None,
+            post_build: None,
+            max_backups: 5,
         }
     }
 }
 
+// Config file names under .easypaper/, in the order they are looked up
+const CONFIG_FILES: &[&str] = &["project.yml", "project.yaml", "project.json"];
+
+/// The project's existing config files, in lookup order
+pub fn config_files(project_dir: &str) -> Vec<PathBuf> {
+    let easypaper_dir = PathBuf::from(project_dir).join(".easypaper");
+    CONFIG_FILES
+        .iter()
+        .map(|name| easypaper_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+fn parse_config_file<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read project config: {}", e))?;
+
+    if is_json(path) {
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse project config {}: {}", path.to_string_lossy(), e))
+    } else {
+        serde_yaml::from_str(&content)
+            .map_err(|e| format!("Failed to parse project config {}: {}", path.to_string_lossy(), e))
+    }
+}
+
+/// The settings exactly as written in the project's config file, without defaults filled in,
+/// and the file they came from; None when the project has no config file
+pub fn raw_config(project_dir: &str) -> Result<Option<(PathBuf, serde_json::Value)>, String> {
+    match config_files(project_dir).into_iter().next() {
+        Some(path) => parse_config_file(&path).map(|value| Some((path, value))),
+        None => Ok(None),
+    }
+}
+
 impl ProjectConfig {
+    /// Reads project.yml, project.yaml or project.json from `.easypaper/`, or the defaults when
+    /// there is none. Several config files are fine only while they agree.
     pub fn load(project_dir: &str) -> Result<Self, String> {
-        let config_path = PathBuf::from(project_dir)
-            .join(".easypaper")
-            .join("project.yml");
-
-        if !config_path.exists() {
+        let paths = config_files(project_dir);
+        let Some(first) = paths.first() else {
             return Ok(ProjectConfig::default());
+        };
+
+        let config = Self::read_file(first)?;
+        for other in &paths[1..] {
+            let same = serde_json::to_value(Self::read_file(other)?).ok() == serde_json::to_value(&config).ok();
+            if !same {
+                return Err(format!(
+                    "Conflicting project configs: {} and {} differ; remove one of them",
+                    first.to_string_lossy(),
+                    other.to_string_lossy()
+                ));
+            }
         }
 
-        let content = std::fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read project config: {}", e))?;
+        // Catch bad patterns here rather than on the next build. A tex_dist_path that doesn't
+        // resolve is left to validate() and the build, since it may only exist on another machine.
+        config.suppress_patterns()?;
+        Ok(config)
+    }
 
-        serde_yaml::from_str(&content)
-            .map_err(|e| format!("Failed to parse project config: {}", e))
+    fn read_file(path: &Path) -> Result<Self, String> {
+        parse_config_file(path)
+    }
+
+    /// Compiled `compile.suppress_warnings` patterns
+    pub fn suppress_patterns(&self) -> Result<Vec<Regex>, String> {
+        self.compile
+            .suppress_warnings
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid suppress_warnings pattern '{}': {}", pattern, e))
+            })
+            .collect()
+    }
+
+    /// Binary directory of `engine.tex_dist_path`: the path itself, its bin/, or TeX Live's
+    /// bin/<platform>/, whichever holds the configured engine (or kpsewhich, for tectonic)
+    pub fn tex_bin_dir(&self) -> Result<Option<PathBuf>, String> {
+        let Some(dist) = self.engine.tex_dist_path.as_deref() else {
+            return Ok(None);
+        };
+        let root = Path::new(dist);
+        if !root.is_dir() {
+            return Err(format!("tex_dist_path '{}' does not exist", dist));
+        }
+
+        let bin = root.join("bin");
+        let platform_dirs = std::fs::read_dir(&bin)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect())
+            .unwrap_or_else(|_| Vec::new());
+        let mut candidates = vec![root.to_path_buf(), bin];
+        candidates.extend(platform_dirs);
+
+        // Either engine is fine when a fallback is configured. TeX distributions don't ship
+        // tectonic, which then comes from PATH and uses the distribution only for its tools.
+        let engines: Vec<&str> = std::iter::once(&self.engine.engine_type)
+            .chain(&self.engine.fallback_engines)
+            .map(|engine| match engine.as_str() {
+                "tectonic" => "kpsewhich",
+                other => other,
+            })
+            .collect();
+        candidates
+            .into_iter()
+            .find(|dir| engines.iter().any(|engine| executable_in(dir, engine).is_some()))
+            .map(Some)
+            .ok_or_else(|| format!("tex_dist_path '{}' contains no {} binary", dist, engines[0]))
+    }
+
+    /// Absolute output directory; `outdir` may be absolute or relative to the project
+    pub fn out_dir(&self, project_dir: &str) -> PathBuf {
+        let outdir = Path::new(&self.compile.outdir);
+        let joined = if outdir.is_absolute() {
+            outdir.to_path_buf()
+        } else {
+            Path::new(project_dir).join(outdir)
+        };
+        // Engines run inside the project directory, so never hand them a relative path
+        std::path::absolute(&joined).unwrap_or(joined)
+    }
+
+    /// Path of a build output for the main file, e.g. `output_path(dir, "pdf")` -> out/main.pdf
+    pub fn output_path(&self, project_dir: &str, extension: &str) -> PathBuf {
+        let stem = Path::new(&self.main)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.main.clone());
+        self.out_dir(project_dir).join(format!("{}.{}", stem, extension))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.main.trim().is_empty() {
+            return Err("'main' must name the main .tex file".to_string());
+        }
+
+        for engine in std::iter::once(&self.engine.engine_type).chain(&self.engine.fallback_engines) {
+            match engine.as_str() {
+                "tectonic" | "latexmk" => {}
+                other => return Err(format!("Unknown engine type: {}", other)),
+            }
+        }
+
+        match self.compile.index_tool.as_deref() {
+            None | Some("makeindex") | Some("xindy") => {}
+            Some(other) => return Err(format!("Unknown index tool: {} (expected makeindex or xindy)", other)),
+        }
+
+        if self.compile.outdir.trim().is_empty() {
+            return Err("'compile.outdir' must not be empty".to_string());
+        }
+
+        self.suppress_patterns()?;
+        self.tex_bin_dir()?;
+
+        Ok(())
     }
 
+    /// Writes every existing config file in its own format, or project.yml for a new project
     pub fn save(&self, project_dir: &str) -> Result<(), String> {
         let easypaper_dir = PathBuf::from(project_dir).join(".easypaper");
         std::fs::create_dir_all(&easypaper_dir)
             .map_err(|e| format!("Failed to create .easypaper directory: {}", e))?;
 
-        let config_path = easypaper_dir.join("project.yml");
-        let content = serde_yaml::to_string(self)
+        let mut paths = config_files(project_dir);
+        if paths.is_empty() {
+            paths.push(easypaper_dir.join("project.yml"));
+        }
+
+        for config_path in paths {
+            let content = if is_json(&config_path) {
+                serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+            } else {
+                serde_yaml::to_string(self).map_err(|e| e.to_string())
+            }
             .map_err(|e| format!("Failed to serialize project config: {}", e))?;
 
-        std::fs::write(&config_path, content)
-            .map_err(|e| format!("Failed to write project config: {}", e))
+            std::fs::write(&config_path, content)
+                .map_err(|e| format!("Failed to write project config: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_outdir_is_joined_to_the_project() {
+        let project = std::env::temp_dir().join("paper");
+        let config = ProjectConfig::default();
+
+        assert_eq!(config.out_dir(&project.to_string_lossy()), project.join("out"));
+        assert_eq!(
+            config.output_path(&project.to_string_lossy(), "pdf"),
+            project.join("out").join("main.pdf")
+        );
+    }
+
+    #[test]
+    fn relative_project_dir_still_gives_an_absolute_outdir() {
+        let config = ProjectConfig::default();
+        let out_dir = config.out_dir("paper");
+
+        assert!(out_dir.is_absolute());
+        assert!(out_dir.ends_with(Path::new("paper").join("out")));
+    }
+
+    #[test]
+    fn absolute_outdir_ignores_the_project() {
+        let project = std::env::temp_dir().join("paper");
+        let build = std::env::temp_dir().join("paper-build");
+        let mut config = ProjectConfig::default();
+        config.compile.outdir = build.to_string_lossy().to_string();
+        config.main = "chapters/thesis.tex".to_string();
+
+        assert_eq!(config.out_dir(&project.to_string_lossy()), build);
+        assert_eq!(
+            config.output_path(&project.to_string_lossy(), "synctex.gz"),
+            build.join("thesis.synctex.gz")
+        );
+    }
+
+    #[test]
+    fn missing_tex_dist_path_still_loads() {
+        let project = tempfile::tempdir().unwrap();
+        let dir = project.path().to_string_lossy().to_string();
+        let mut config = ProjectConfig::default();
+        config.engine.tex_dist_path = Some(project.path().join("no-such-texlive").to_string_lossy().to_string());
+        config.save(&dir).unwrap();
+
+        let loaded = ProjectConfig::load(&dir).unwrap();
+        assert!(loaded.validate().is_err());
+        assert!(loaded.tex_bin_dir().is_err());
+    }
+
+    #[test]
+    fn tectonic_finds_the_distribution_by_its_tools() {
+        let dist = tempfile::tempdir().unwrap();
+        let bin = dist.path().join("bin").join("x86_64-linux");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("kpsewhich"), "").unwrap();
+        let mut config = ProjectConfig::default();
+        config.engine.tex_dist_path = Some(dist.path().to_string_lossy().to_string());
+
+        assert_eq!(config.tex_bin_dir().unwrap(), Some(bin.clone()));
+        config.engine.engine_type = "latexmk".to_string();
+        assert!(config.tex_bin_dir().is_err());
+        std::fs::write(bin.join("latexmk"), "").unwrap();
+        assert_eq!(config.tex_bin_dir().unwrap(), Some(bin));
     }
 }
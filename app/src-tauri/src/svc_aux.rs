@@ -0,0 +1,129 @@
+use crate::project::ProjectConfig;
+use crate::svc_file::ApiResponse;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuxLabel {
+    pub name: String,
+    /// Printed number, e.g. "2.1" for a subsection
+    pub number: String,
+    pub page: String,
+    /// Caption or heading text when hyperref records it
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuxData {
+    pub labels: Vec<AuxLabel>,
+    /// Cited keys in order of first citation
+    pub citations: Vec<String>,
+    /// Bibliography databases from \bibdata, without the .bib extension
+    pub bibdata: Vec<String>,
+}
+
+fn aux_command_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\(newlabel|citation|bibdata|@input)\{([^}]*)\}").unwrap())
+}
+
+// Contents of the top-level `{...}` groups at the start of `text`, e.g. `{{1}{2}}` -> ["{1}{2}"]
+fn leading_groups(text: &str) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => {
+                if depth == 0 {
+                    start = i + 1;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(&text[start..i]);
+                }
+            }
+            c if depth == 0 && !c.is_whitespace() => break,
+            _ => {}
+        }
+    }
+
+    groups
+}
+
+/// Reads an .aux file and any it pulls in with \@input (one per \include'd file)
+pub(crate) fn read_aux(path: &Path, data: &mut AuxData, depth: usize) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in content.lines() {
+        for caps in aux_command_regex().captures_iter(line) {
+            let value = caps[2].trim();
+            match &caps[1] {
+                "newlabel" => {
+                    // cleveref writes a duplicate `name@cref` entry for every label
+                    if value.ends_with("@cref") {
+                        continue;
+                    }
+                    let rest = &line[caps.get(0).unwrap().end()..];
+                    let Some(outer) = leading_groups(rest).first().copied() else {
+                        continue;
+                    };
+                    let fields = leading_groups(outer);
+                    data.labels.push(AuxLabel {
+                        name: value.to_string(),
+                        number: fields.first().unwrap_or(&"").to_string(),
+                        page: fields.get(1).unwrap_or(&"").to_string(),
+                        title: fields.get(2).filter(|t| !t.is_empty()).map(|t| t.to_string()),
+                    });
+                }
+                "citation" => {
+                    for key in value.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                        if !data.citations.iter().any(|c| c == key) {
+                            data.citations.push(key.to_string());
+                        }
+                    }
+                }
+                "bibdata" => {
+                    for bib in value.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+                        if !data.bibdata.iter().any(|existing| existing == bib) {
+                            data.bibdata.push(bib.to_string());
+                        }
+                    }
+                }
+                // Nested aux paths are relative to the directory of the main .aux
+                _ if depth < 8 => {
+                    if let Some(dir) = path.parent() {
+                        read_aux(&dir.join(value), data, depth + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Labels, citations and bibliography files recorded in the last build's .aux file.
+/// Empty before the first compile.
+#[tauri::command]
+pub fn aux_parse(project_dir: String) -> ApiResponse<AuxData> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let mut data = AuxData::default();
+    read_aux(&config.output_path(&project_dir, "aux"), &mut data, 0);
+
+    ApiResponse::success(data)
+}
@@ -0,0 +1,782 @@
+use crate::document::{resolve_document, strip_comment, ResolvedDoc};
+use crate::project::ProjectConfig;
+use crate::svc_file::{write_atomic, ApiResponse};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BibIssue {
+    pub key: Option<String>,
+    pub line: u32,
+    pub kind: String, // "missing_field", "duplicate_key", "unescaped_char" or "malformed"
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedBibEntry {
+    pub key: String,
+    pub file: String,
+    pub line: u32,
+}
+
+/// A bibliography entry summarised for \cite completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BibEntry {
+    pub key: String,
+    /// Lowercased entry type, e.g. "article"
+    pub entry_type: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// `year`, or the year of a biblatex `date`
+    pub year: Option<String>,
+    pub file: String,
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BibFormatOptions {
+    /// "key" or "year"; entries keep their order when unset
+    pub sort_by: Option<String>,
+    /// Fold entries with a repeated key into the first one instead of flagging them
+    pub merge_duplicates: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BibField {
+    pub name: String,
+    /// The value as written, including its braces or quotes
+    pub raw: String,
+    pub line: u32,
+}
+
+impl BibField {
+    /// The value without its outer delimiters
+    pub fn text(&self) -> String {
+        let raw = self.raw.trim();
+        let inner = if (raw.starts_with('{') && raw.ends_with('}'))
+            || (raw.starts_with('"') && raw.ends_with('"') && raw.len() >= 2)
+        {
+            &raw[1..raw.len() - 1]
+        } else {
+            raw
+        };
+        inner.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BibRecord {
+    /// Entry type as written (e.g. "Article")
+    pub entry_type: String,
+    pub key: String,
+    pub fields: Vec<BibField>,
+    pub line: u32,
+}
+
+impl BibRecord {
+    pub fn field(&self, name: &str) -> Option<&BibField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
+
+/// A top-level piece of a .bib file, in file order
+#[derive(Debug, Clone)]
+pub(crate) enum BibBlock {
+    /// Index into `ParsedBib::entries`
+    Entry(usize),
+    /// @string/@preamble/@comment items, malformed entries and the text between items, verbatim
+    Raw(String),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ParsedBib {
+    pub entries: Vec<BibRecord>,
+    pub errors: Vec<(u32, String)>,
+    pub blocks: Vec<BibBlock>,
+}
+
+// Tolerant .bib parser: malformed entries are recorded as errors and skipped
+pub(crate) fn parse_bib(content: &str) -> ParsedBib {
+    let chars: Vec<char> = content.chars().collect();
+    let mut parsed = ParsedBib::default();
+    let mut parser = BibParser { chars: &chars, pos: 0, line: 1 };
+    let raw = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+    let mut gap_start = 0;
+
+    while parser.pos < chars.len() {
+        if chars[parser.pos] != '@' {
+            parser.advance();
+            continue;
+        }
+
+        let start = parser.pos;
+        let start_line = parser.line;
+        if start > gap_start {
+            parsed.blocks.push(BibBlock::Raw(raw(gap_start, start)));
+        }
+        match parser.parse_item() {
+            Ok(Some(record)) => {
+                parsed.blocks.push(BibBlock::Entry(parsed.entries.len()));
+                parsed.entries.push(record);
+            }
+            Ok(None) => parsed.blocks.push(BibBlock::Raw(raw(start, parser.pos))),
+            Err(message) => {
+                parsed.errors.push((start_line, message));
+                // Resume at the next entry
+                parser.pos = start + 1;
+                parser.line = start_line;
+                while parser.pos < chars.len() && chars[parser.pos] != '@' {
+                    parser.advance();
+                }
+                parsed.blocks.push(BibBlock::Raw(raw(start, parser.pos)));
+            }
+        }
+        gap_start = parser.pos;
+    }
+    if chars.len() > gap_start {
+        parsed.blocks.push(BibBlock::Raw(raw(gap_start, chars.len())));
+    }
+
+    parsed
+}
+
+struct BibParser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    line: u32,
+}
+
+impl BibParser<'_> {
+    fn advance(&mut self) {
+        if self.chars.get(self.pos) == Some(&'\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.advance();
+        }
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || "_-:./+'".contains(c) {
+                ident.push(c);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        ident
+    }
+
+    // Parses one @-item; returns None for @string/@preamble/@comment
+    fn parse_item(&mut self) -> Result<Option<BibRecord>, String> {
+        let line = self.line;
+        self.advance(); // '@'
+        let entry_type = self.read_identifier();
+        if entry_type.is_empty() {
+            return Err("Expected an entry type after '@'".to_string());
+        }
+        self.skip_whitespace();
+
+        let close = match self.peek() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => return Err(format!("Expected '{{' after @{}", entry_type)),
+        };
+
+        if matches!(entry_type.to_lowercase().as_str(), "string" | "preamble" | "comment") {
+            self.skip_group(close)?;
+            return Ok(None);
+        }
+        self.advance();
+
+        self.skip_whitespace();
+        let mut key = String::new();
+        while let Some(c) = self.peek() {
+            if c == ',' || c == close || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            self.advance();
+        }
+        if key.is_empty() {
+            return Err(format!("@{} entry has no citation key", entry_type));
+        }
+
+        let mut fields = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.advance();
+                    continue;
+                }
+                Some(c) if c == close => {
+                    self.advance();
+                    break;
+                }
+                None => return Err(format!("Entry '{}' is not closed", key)),
+                _ => {}
+            }
+
+            let field_line = self.line;
+            let name = self.read_identifier().to_lowercase();
+            if name.is_empty() {
+                return Err(format!("Malformed field in entry '{}'", key));
+            }
+            self.skip_whitespace();
+            if self.peek() != Some('=') {
+                return Err(format!("Expected '=' after field '{}' in entry '{}'", name, key));
+            }
+            self.advance();
+            let raw = self.read_value(close)?;
+            fields.push(BibField { name, raw, line: field_line });
+        }
+
+        Ok(Some(BibRecord { entry_type, key, fields, line }))
+    }
+
+    // Reads a possibly '#'-concatenated field value up to the next ',' or closing delimiter
+    fn read_value(&mut self, close: char) -> Result<String, String> {
+        let start = self.pos;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => self.skip_group('}')?,
+                Some('"') => {
+                    self.advance();
+                    let mut depth = 0;
+                    loop {
+                        match self.peek() {
+                            None => return Err("Unterminated quoted value".to_string()),
+                            Some('{') => depth += 1,
+                            Some('}') => depth -= 1,
+                            Some('"') if depth == 0 => {
+                                self.advance();
+                                break;
+                            }
+                            _ => {}
+                        }
+                        self.advance();
+                    }
+                }
+                Some(_) => {
+                    let word = self.read_identifier();
+                    if word.is_empty() {
+                        return Err("Expected a field value".to_string());
+                    }
+                }
+                None => return Err("Unexpected end of file in field value".to_string()),
+            }
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.advance();
+                continue;
+            }
+            break;
+        }
+
+        match self.peek() {
+            Some(',') => {}
+            Some(c) if c == close => {}
+            _ => return Err("Expected ',' between fields".to_string()),
+        }
+        Ok(self.chars[start..self.pos].iter().collect::<String>().trim().to_string())
+    }
+
+    // Skips a balanced group starting at the opening delimiter
+    fn skip_group(&mut self, close: char) -> Result<(), String> {
+        let open = self.peek().unwrap_or('{');
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    self.advance();
+                    return Ok(());
+                }
+            }
+            self.advance();
+        }
+        Err("Unbalanced braces".to_string())
+    }
+}
+
+// Required fields per entry type; "a|b" means either field satisfies the requirement
+fn required_fields(entry_type: &str) -> &'static [&'static str] {
+    match entry_type {
+        "article" => &["author", "title", "journal", "year"],
+        "book" => &["author|editor", "title", "publisher", "year"],
+        "inbook" => &["author|editor", "title", "chapter|pages", "publisher", "year"],
+        "incollection" => &["author", "title", "booktitle", "publisher", "year"],
+        "inproceedings" | "conference" => &["author", "title", "booktitle", "year"],
+        "mastersthesis" | "phdthesis" => &["author", "title", "school", "year"],
+        "techreport" => &["author", "title", "institution", "year"],
+        "proceedings" => &["title", "year"],
+        "unpublished" => &["author", "title", "note"],
+        "manual" | "booklet" => &["title"],
+        _ => &[],
+    }
+}
+
+// Fields that hold URLs or identifiers, or are never typeset by the standard styles
+const UNCHECKED_FIELDS: &[&str] = &[
+    "url", "doi", "eprint", "file", "urldate", "howpublished", "abstract", "keywords", "annote",
+];
+
+// True when the raw value joins several parts with a top-level '#'
+fn is_concatenated(raw: &str) -> bool {
+    let mut depth = 0;
+    let mut in_quotes = false;
+    for c in raw.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' if depth == 0 => in_quotes = !in_quotes,
+            '#' if depth == 0 && !in_quotes => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn unescaped_chars(value: &str) -> Vec<char> {
+    let mut found = Vec::new();
+    let mut in_math = false;
+    let mut prev = ' ';
+    for c in value.chars() {
+        if c == '$' && prev != '\\' {
+            in_math = !in_math;
+        } else if !in_math && prev != '\\' && "&%#_".contains(c) && !found.contains(&c) {
+            found.push(c);
+        }
+        prev = c;
+    }
+    found
+}
+
+/// Report missing required fields, duplicate keys and characters that break compilation
+#[tauri::command]
+pub fn bib_lint(path: String) -> ApiResponse<Vec<BibIssue>> {
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read bibliography: {}", e)),
+    };
+
+    let parsed = parse_bib(&content);
+    let mut issues: Vec<BibIssue> = parsed
+        .errors
+        .iter()
+        .map(|(line, message)| BibIssue {
+            key: None,
+            line: *line,
+            kind: "malformed".to_string(),
+            message: message.clone(),
+        })
+        .collect();
+
+    // BibTeX compares keys case-insensitively
+    let mut seen: HashMap<String, u32> = HashMap::new();
+
+    for entry in &parsed.entries {
+        if let Some(first_line) = seen.get(&entry.key.to_lowercase()) {
+            issues.push(BibIssue {
+                key: Some(entry.key.clone()),
+                line: entry.line,
+                kind: "duplicate_key".to_string(),
+                message: format!("Duplicate key '{}' (first defined on line {})", entry.key, first_line),
+            });
+        } else {
+            seen.insert(entry.key.to_lowercase(), entry.line);
+        }
+
+        for required in required_fields(&entry.entry_type.to_lowercase()) {
+            let present = required
+                .split('|')
+                .any(|name| entry.field(name).is_some_and(|f| !f.text().is_empty()));
+            if !present {
+                issues.push(BibIssue {
+                    key: Some(entry.key.clone()),
+                    line: entry.line,
+                    kind: "missing_field".to_string(),
+                    message: format!(
+                        "@{} '{}' is missing required field '{}'",
+                        entry.entry_type.to_lowercase(),
+                        entry.key,
+                        required.replace('|', "' or '")
+                    ),
+                });
+            }
+        }
+
+        for field in &entry.fields {
+            // Concatenated values (jan # " 1") legitimately contain '#'
+            if UNCHECKED_FIELDS.contains(&field.name.as_str()) || is_concatenated(&field.raw) {
+                continue;
+            }
+            for c in unescaped_chars(&field.text()) {
+                issues.push(BibIssue {
+                    key: Some(entry.key.clone()),
+                    line: field.line,
+                    kind: "unescaped_char".to_string(),
+                    message: format!("Unescaped '{}' in field '{}' (write \\{})", c, field.name, c),
+                });
+            }
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    ApiResponse::success(issues)
+}
+
+// Bibliography files named by \bibliography/\addbibresource, or every .bib in the project root
+pub(crate) fn project_bib_files(project_dir: &Path, doc: &ResolvedDoc) -> Vec<String> {
+    let mut files = Vec::new();
+
+    for (_, text) in doc.source_lines() {
+        for caps in bib_resource_regex().captures_iter(strip_comment(text)) {
+            for name in caps[1].split(',') {
+                let mut name = name.trim().to_string();
+                if !name.ends_with(".bib") {
+                    name.push_str(".bib");
+                }
+                if project_dir.join(&name).is_file() && !files.contains(&name) {
+                    files.push(name);
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        if let Ok(entries) = std::fs::read_dir(project_dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.ends_with(".bib") {
+                    files.push(name);
+                }
+            }
+        }
+        files.sort();
+    }
+
+    files
+}
+
+/// Every key cited in the document; contains "*" when \nocite{*} is used
+pub(crate) fn cited_keys(doc: &ResolvedDoc) -> HashSet<String> {
+    let mut keys = HashSet::new();
+
+    for (_, text) in doc.source_lines() {
+        for caps in cite_regex().captures_iter(strip_comment(text)) {
+            for key in caps[1].split(',') {
+                let key = key.trim();
+                if !key.is_empty() {
+                    keys.insert(key.to_string());
+                }
+            }
+        }
+    }
+
+    keys
+}
+
+fn bib_resource_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\(?:bibliography|addbibresource)(?:\[[^\]]*\])?\{([^}]+)\}").unwrap())
+}
+
+fn cite_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\[a-zA-Z]*cite[a-zA-Z]*\*?(?:\s*\[[^\]]*\]){0,2}\s*\{([^}]*)\}").unwrap())
+}
+
+/// List bibliography entries that are never cited in the document
+#[tauri::command]
+pub fn bib_find_unused(project_dir: String) -> ApiResponse<Vec<UnusedBibEntry>> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let project_path = Path::new(&project_dir);
+    let doc = match resolve_document(project_path, &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    match uncited_entries(project_path, &doc, &project_bib_files(project_path, &doc)) {
+        Ok(unused) => ApiResponse::success(unused),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+/// Entries of `bib_files` the document never cites
+pub(crate) fn uncited_entries(project_dir: &Path, doc: &ResolvedDoc, bib_files: &[String]) -> Result<Vec<UnusedBibEntry>, String> {
+    let cited = cited_keys(doc);
+    if cited.contains("*") {
+        // \nocite{*} pulls in every entry
+        return Ok(vec![]);
+    }
+
+    let mut unused = Vec::new();
+    for file in bib_files {
+        let content = std::fs::read_to_string(project_dir.join(file))
+            .map_err(|e| format!("Failed to read {}: {}", file, e))?;
+
+        for entry in &parse_bib(&content).entries {
+            if !cited.contains(&entry.key) {
+                unused.push(UnusedBibEntry {
+                    key: entry.key.clone(),
+                    file: file.clone(),
+                    line: entry.line,
+                });
+            }
+        }
+    }
+
+    Ok(unused)
+}
+
+// `year`, or the year of a biblatex `date`
+fn record_year(record: &BibRecord) -> Option<String> {
+    record.field("year").map(display_text).or_else(|| {
+        record
+            .field("date")
+            .map(display_text)
+            .and_then(|date| date.get(..4).map(str::to_string))
+    })
+}
+
+// Field text without the case-protecting braces, e.g. "{BERT}: Pre-training" -> "BERT: Pre-training"
+fn display_text(field: &BibField) -> String {
+    field.text().replace(['{', '}'], "")
+}
+
+/// Every entry in the project's bibliography files, for citation autocomplete
+#[tauri::command]
+pub fn bib_keys(project_dir: String) -> ApiResponse<Vec<BibEntry>> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let project_path = Path::new(&project_dir);
+    let doc = match resolve_document(project_path, &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let mut entries = Vec::new();
+    for file in project_bib_files(project_path, &doc) {
+        let content = match std::fs::read_to_string(project_path.join(&file)) {
+            Ok(content) => content,
+            Err(e) => return ApiResponse::error(format!("Failed to read {}: {}", file, e)),
+        };
+
+        // Malformed entries are already dropped by the parser
+        for record in parse_bib(&content).entries {
+            let year = record_year(&record);
+
+            entries.push(BibEntry {
+                entry_type: record.entry_type.to_lowercase(),
+                title: record.field("title").map(display_text),
+                author: record.field("author").map(display_text),
+                year,
+                file: file.clone(),
+                line: record.line,
+                key: record.key,
+            });
+        }
+    }
+
+    ApiResponse::success(entries)
+}
+
+/// Rewrites a .bib file in a consistent layout: lowercase entry types and field names, fields
+/// sorted and aligned, entries optionally sorted by key or year. Duplicate keys are merged or
+/// flagged with a comment. @string/@preamble items, comments and malformed entries are kept
+/// verbatim. Returns the new text.
+#[tauri::command]
+pub fn bib_format(path: String, opts: Option<BibFormatOptions>) -> ApiResponse<String> {
+    let opts = opts.unwrap_or_default();
+    if let Some(sort_by) = opts.sort_by.as_deref().filter(|s| !matches!(*s, "key" | "year")) {
+        return ApiResponse::error(format!("Unknown sort order: {} (expected key or year)", sort_by));
+    }
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read bibliography: {}", e)),
+    };
+
+    let formatted = format_bib(parse_bib(&content), &opts);
+    match write_atomic(Path::new(&path), &formatted) {
+        Ok(()) => ApiResponse::success(formatted),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+const DUPLICATE_KEY_NOTE: &str = "% Duplicate key: ";
+
+fn format_bib(parsed: ParsedBib, opts: &BibFormatOptions) -> String {
+    let ParsedBib { mut entries, blocks, .. } = parsed;
+
+    // BibTeX compares keys case-insensitively; the first entry with a key keeps it
+    let mut first_with_key: HashMap<String, usize> = HashMap::new();
+    let mut duplicates = HashSet::new();
+    for index in 0..entries.len() {
+        let key = entries[index].key.to_lowercase();
+        match first_with_key.get(&key) {
+            Some(&first) => {
+                duplicates.insert(index);
+                if opts.merge_duplicates {
+                    let extra: Vec<BibField> = entries[index]
+                        .fields
+                        .iter()
+                        .filter(|field| entries[first].field(&field.name).is_none())
+                        .cloned()
+                        .collect();
+                    entries[first].fields.extend(extra);
+                }
+            }
+            None => {
+                first_with_key.insert(key, index);
+            }
+        }
+    }
+
+    let mut raw_blocks = Vec::new();
+    let mut order = Vec::new();
+    let mut out_blocks = Vec::new();
+    for block in blocks {
+        // Notes from an earlier run are written afresh for the duplicates that remain
+        let block = match block {
+            BibBlock::Raw(text) => BibBlock::Raw(
+                text.lines()
+                    .filter(|line| !line.trim_start().starts_with(DUPLICATE_KEY_NOTE))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            block => block,
+        };
+        match block {
+            BibBlock::Entry(index) if opts.merge_duplicates && duplicates.contains(&index) => {}
+            BibBlock::Entry(index) if opts.sort_by.is_some() => order.push(index),
+            BibBlock::Raw(text) if text.trim().is_empty() => {}
+            // Sorting moves entries below everything else, so @string definitions stay first
+            BibBlock::Raw(text) if opts.sort_by.is_some() => raw_blocks.push(text.trim().to_string()),
+            block => out_blocks.push(block),
+        }
+    }
+
+    match opts.sort_by.as_deref() {
+        Some("year") => order.sort_by_cached_key(|&i| {
+            let year = record_year(&entries[i]);
+            (year.is_none(), year, entries[i].key.to_lowercase())
+        }),
+        Some(_) => order.sort_by_cached_key(|&i| entries[i].key.to_lowercase()),
+        None => {}
+    }
+    if opts.sort_by.is_some() {
+        out_blocks = raw_blocks
+            .into_iter()
+            .map(BibBlock::Raw)
+            .chain(order.into_iter().map(BibBlock::Entry))
+            .collect();
+    }
+
+    let mut parts = Vec::new();
+    for block in out_blocks {
+        match block {
+            BibBlock::Raw(text) => parts.push(text.trim().to_string()),
+            BibBlock::Entry(index) => {
+                let mut text = format_entry(&entries[index]);
+                if duplicates.contains(&index) {
+                    text = format!("{}{}\n{}", DUPLICATE_KEY_NOTE, entries[index].key, text);
+                }
+                parts.push(text);
+            }
+        }
+    }
+
+    let mut formatted = parts.join("\n\n");
+    formatted.push('\n');
+    formatted
+}
+
+fn format_entry(record: &BibRecord) -> String {
+    let mut fields: Vec<&BibField> = record.fields.iter().collect();
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+    let width = fields.iter().map(|f| f.name.chars().count()).max().unwrap_or(0);
+
+    let mut text = format!("@{}{{{}", record.entry_type.to_lowercase(), record.key);
+    for field in fields {
+        // Continuation lines of multi-line values are re-indented under the field
+        let value = field
+            .raw
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(&format!("\n  {}   ", " ".repeat(width)));
+        text.push_str(&format!(",\n  {:<width$} = {}", field.name, value, width = width));
+    }
+    text.push_str("\n}");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUPLICATES: &str = "@string{acm = \"ACM\"}\n\n\
+        @Article{smith2020, Title = {First}, year = 2020}\n\n\
+        % keep this comment\n\
+        @article{Smith2020, title = {Second}, author = {Smith}}\n\n\
+        @book{alpha, title = {Alpha}, year = 2019}\n";
+
+    fn format_twice(opts: &BibFormatOptions) -> (String, String) {
+        let once = format_bib(parse_bib(DUPLICATES), opts);
+        let twice = format_bib(parse_bib(&once), opts);
+        (once, twice)
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        for sort_by in [None, Some("key"), Some("year")] {
+            let opts = BibFormatOptions {
+                sort_by: sort_by.map(str::to_string),
+                merge_duplicates: false,
+            };
+            let (once, twice) = format_twice(&opts);
+            assert_eq!(once, twice, "sort_by {:?}", sort_by);
+            assert_eq!(once.matches("% Duplicate key: Smith2020").count(), 1);
+            assert!(once.contains("% keep this comment"));
+        }
+    }
+
+    #[test]
+    fn merged_duplicates_leave_no_note() {
+        let opts = BibFormatOptions {
+            sort_by: None,
+            merge_duplicates: true,
+        };
+        let (once, twice) = format_twice(&opts);
+        assert_eq!(once, twice);
+        assert!(!once.contains("% Duplicate key"));
+        assert!(once.contains("author = {Smith}"));
+    }
+}
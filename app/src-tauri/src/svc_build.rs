@@ -1,8 +1,32 @@
+use crate::cache::{
+    cache_dir, changed_files, hash_hex, is_source_file, read_json, snapshot_sources, unix_timestamp, write_json,
+    SourceSnapshot,
+};
+use crate::document::{extract_preamble, resolve_document, strip_comment, subfile_parent};
+use crate::error_hints::hint_for;
 use crate::project::ProjectConfig;
-use crate::svc_file::ApiResponse;
+use crate::svc_engine::{executable_in, find_in_path, path_with_dir};
+use crate::svc_file::{read_base64, ApiResponse};
+use crate::svc_project::{is_trusted, ProjectState};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::process::Command;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::cell::RefCell;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager, State, Window};
+use walkdir::WalkDir;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildResult {
@@ -11,6 +35,16 @@ pub struct BuildResult {
     pub log_path: Option<String>,
     pub errors: Vec<BuildError>,
     pub warnings: Vec<BuildWarning>,
+    /// Warnings hidden by `compile.suppress_warnings`
+    pub suppressed_count: usize,
+    pub undefined_refs: Vec<UndefinedRef>,
+    pub page_count: Option<u32>,
+    /// The engine that ran; differs from the configured one after a fallback
+    pub engine_used: String,
+    /// Whether a cached precompiled preamble was used
+    pub precompiled_preamble: bool,
+    /// Console output of the pre-/post-build hooks, when the project defines any
+    pub hook_output: Option<String>,
     pub duration_ms: u128,
 }
 
@@ -19,6 +53,8 @@ pub struct BuildError {
     pub file: Option<String>,
     pub line: Option<u32>,
     pub message: String,
+    /// Plain-language suggestion for common errors (see `error_hints`)
+    pub hint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,94 +64,1186 @@ pub struct BuildWarning {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UndefinedRef {
+    pub kind: String, // "reference" or "citation"
+    pub key: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildStat {
+    pub timestamp: u64,
+    pub duration_ms: u128,
+    pub page_count: Option<u32>,
+    pub error_count: usize,
+    pub warning_count: usize,
+}
+
+/// A build error or warning as shown in the editor gutter of its file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: String, // "error" or "warning"
+    pub line: Option<u32>,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+// Number of builds kept in the history file
+const MAX_BUILD_HISTORY: usize = 200;
+
 #[tauri::command]
-pub fn build_compile(project_dir: String) -> ApiResponse<BuildResult> {
-    let start = std::time::Instant::now();
+pub fn build_compile(
+    project_dir: String,
+    include_only: Option<Vec<String>>,
+    state: State<'_, ProjectState>,
+) -> ApiResponse<BuildResult> {
+    // Load project configuration (cached when the project is open)
+    let config = match state.config_for(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    if let Some((code, message)) = trust_required(&project_dir, &config) {
+        return ApiResponse::error_with_code(code, message);
+    }
+
+    match compile_project(&project_dir, &config, include_only.as_deref()) {
+        Ok(build_result) => ApiResponse::success(build_result),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompiledPdf {
+    #[serde(flatten)]
+    pub build: BuildResult,
+    /// Base64-encoded PDF, present after a successful build unless the PDF is too large
+    pub pdf_base64: Option<String>,
+    /// The PDF exceeded the IPC size limit; read it from `pdf_path` instead
+    pub pdf_omitted: bool,
+}
 
-    // Load project configuration
-    let config = match ProjectConfig::load(&project_dir) {
+/// Like `build_compile`, but also returns the PDF itself so a preview needs no second read
+#[tauri::command]
+pub fn build_compile_inline(project_dir: String, state: State<'_, ProjectState>) -> ApiResponse<CompiledPdf> {
+    let config = match state.config_for(&project_dir) {
         Ok(cfg) => cfg,
         Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
     };
 
-    // Compile based on engine type
-    let result = match config.engine.engine_type.as_str() {
-        "tectonic" => compile_with_tectonic(&project_dir, &config),
-        "latexmk" => compile_with_latexmk(&project_dir, &config),
+    if let Some((code, message)) = trust_required(&project_dir, &config) {
+        return ApiResponse::error_with_code(code, message);
+    }
+
+    let build = match compile_project(&project_dir, &config, None) {
+        Ok(build) => build,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let mut pdf_base64 = None;
+    let mut pdf_omitted = false;
+    if let Some(pdf_path) = build.pdf_path.as_deref().filter(|_| build.success) {
+        match read_base64(Path::new(pdf_path)) {
+            Ok(Some(pdf)) => pdf_base64 = Some(pdf),
+            Ok(None) => pdf_omitted = true,
+            Err(e) => return ApiResponse::error(e),
+        }
+    }
+
+    ApiResponse::success(CompiledPdf {
+        build,
+        pdf_base64,
+        pdf_omitted,
+    })
+}
+
+pub const SHELL_ESCAPE_CONFIRMATION_CODE: &str = "shell_escape_requires_confirmation";
+pub const BUILD_HOOKS_CONFIRMATION_CODE: &str = "build_hooks_require_confirmation";
+
+// Shell escape and build hooks let a project run arbitrary commands, so both need
+// explicit consent (project_trust). Returns the error code and message when blocked.
+fn trust_required(project_dir: &str, config: &ProjectConfig) -> Option<(&'static str, String)> {
+    let has_hooks = config.pre_build.is_some() || config.post_build.is_some();
+    if !(config.compile.shell_escape || has_hooks) || is_trusted(project_dir) {
+        return None;
+    }
+
+    if config.compile.shell_escape {
+        Some((
+            SHELL_ESCAPE_CONFIRMATION_CODE,
+            "This project enables shell escape, which lets it run arbitrary commands. Trust the project to compile it."
+                .to_string(),
+        ))
+    } else {
+        Some((
+            BUILD_HOOKS_CONFIRMATION_CODE,
+            "This project defines build hooks, which run arbitrary commands. Trust the project to compile it."
+                .to_string(),
+        ))
+    }
+}
+
+fn active_builds() -> &'static Mutex<HashSet<PathBuf>> {
+    static ACTIVE: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Exclusive use of a project's output directory, released when dropped
+struct BuildLock(PathBuf);
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        // A build that panicked must not keep the project locked
+        let mut active = active_builds().lock().unwrap_or_else(|e| e.into_inner());
+        active.remove(&self.0);
+    }
+}
+
+// Two engines writing the same outdir at once can leave a corrupt PDF, so a second build fails fast
+fn lock_project(project_dir: &str) -> Result<BuildLock, String> {
+    let key = std::fs::canonicalize(project_dir).unwrap_or_else(|_| PathBuf::from(project_dir));
+    let mut active = active_builds().lock().unwrap_or_else(|e| e.into_inner());
+    if !active.insert(key.clone()) {
+        return Err("Build already in progress".to_string());
+    }
+    Ok(BuildLock(key))
+}
+
+// Full build of the main document, shared by the blocking and background commands.
+// `include_only` limits which \include'd files are compiled.
+fn compile_project(project_dir: &str, config: &ProjectConfig, include_only: Option<&[String]>) -> Result<BuildResult, String> {
+    let start = std::time::Instant::now();
+    let _lock = lock_project(project_dir)?;
+
+    // A renamed or deleted main file would otherwise surface as a confusing engine log
+    if !Path::new(project_dir).join(&config.main).is_file() {
+        return Err(format!("Main file '{}' not found", config.main));
+    }
+
+    // The commands check this up front; the config may have changed since for queued builds
+    if let Some((_, message)) = trust_required(project_dir, config) {
+        return Err(message);
+    }
+
+    let mut hook_output = String::new();
+    if let Some(command) = &config.pre_build {
+        let (success, output) = run_hook(project_dir, command)?;
+        hook_output.push_str(&output);
+        if !success {
+            return Err(format!("Pre-build hook failed: {}\n{}", command, output));
+        }
+    }
+
+    // Dropping the TempDir removes it, whether or not the build succeeds
+    let temp_dir = if config.compile.use_temp_builddir {
+        let dir = tempfile::Builder::new()
+            .prefix("easypaper-build-")
+            .tempdir()
+            .map_err(|e| format!("Failed to create temporary build directory: {}", e))?;
+        Some(dir)
+    } else {
+        None
+    };
+    let mut engine_config = config.clone();
+    if let Some(dir) = &temp_dir {
+        engine_config.compile.outdir = dir.path().to_string_lossy().to_string();
+        // Cross-references, citations and \includeonly's skipped files come from earlier runs
+        copy_rerun_files(&config.out_dir(project_dir), dir.path())?;
+    }
+
+    let mut build_result = match include_only {
+        Some(only) => run_engine_include_only(project_dir, &engine_config, only, start)?,
+        None => run_engine(project_dir, &engine_config, start)?,
+    };
+    if temp_dir.is_some() {
+        // Copy rather than rename: the temp directory is often on another filesystem
+        transfer_build_outputs(project_dir, &engine_config, config, &mut build_result, true)?;
+        copy_rerun_files(&engine_config.out_dir(project_dir), &config.out_dir(project_dir))?;
+    }
+
+    if let (true, Some(command)) = (build_result.success, &config.post_build) {
+        let (success, output) = run_hook(project_dir, command)?;
+        hook_output.push_str(&output);
+        if !success {
+            build_result.warnings.push(BuildWarning {
+                file: None,
+                line: None,
+                message: format!("Post-build hook failed: {}", command),
+            });
+        }
+    }
+
+    if config.pre_build.is_some() || config.post_build.is_some() {
+        build_result.hook_output = Some(hook_output);
+    }
+
+    // Diagnostics, history and the source snapshot are best-effort and never fail the build
+    let _ = save_diagnostics(project_dir, config, &build_result);
+    if build_result.success {
+        let _ = record_build_stat(project_dir, &build_result);
+        let _ = save_build_snapshot(project_dir, config);
+    }
+
+    Ok(build_result)
+}
+
+pub const BUILD_FINISHED_EVENT: &str = "build://finished";
+pub const BUILD_FAILED_EVENT: &str = "build://failed";
+pub const BUILD_CANCELLED_EVENT: &str = "build://cancelled";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildFinishedEvent {
+    pub id: u64,
+    pub result: BuildResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildFailedEvent {
+    pub id: u64,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildCancelledEvent {
+    pub id: u64,
+}
+
+/// Background builds, at most one running per project with one more queued behind it
+#[derive(Default)]
+pub struct BuildQueue {
+    next_id: AtomicU64,
+    // Projects with a running build, mapped to the id of the build waiting behind it
+    projects: Arc<Mutex<HashMap<PathBuf, Option<u64>>>>,
+}
+
+/// Start a build on a background thread and return its id right away.
+/// The outcome arrives as a `build://finished` or `build://failed` event; a queued
+/// build replaced by a newer request gets `build://cancelled`.
+#[tauri::command]
+pub fn build_compile_async(
+    project_dir: String,
+    window: Window,
+    queue: State<'_, BuildQueue>,
+    state: State<'_, ProjectState>,
+) -> ApiResponse<u64> {
+    match state.config_for(&project_dir) {
+        Ok(config) => {
+            if let Some((code, message)) = trust_required(&project_dir, &config) {
+                return ApiResponse::error_with_code(code, message);
+            }
+        }
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    }
+
+    let id = queue.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let key = Path::new(&project_dir)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&project_dir));
+
+    {
+        let mut projects = match queue.projects.lock() {
+            Ok(projects) => projects,
+            Err(e) => return ApiResponse::error(format!("Failed to access build queue: {}", e)),
+        };
+
+        // A build is already running: wait behind it, replacing any older queued build
+        if let Some(queued) = projects.get_mut(&key) {
+            if let Some(superseded) = queued.replace(id) {
+                let _ = window.emit(BUILD_CANCELLED_EVENT, BuildCancelledEvent { id: superseded });
+            }
+            return ApiResponse::success(id);
+        }
+        projects.insert(key.clone(), None);
+    }
+
+    let projects = Arc::clone(&queue.projects);
+    std::thread::spawn(move || {
+        let mut id = id;
+        loop {
+            // Reloaded for every build so a queued build sees config changes
+            let result = window
+                .state::<ProjectState>()
+                .config_for(&project_dir)
+                .map_err(|e| format!("Failed to load project config: {}", e))
+                .and_then(|config| compile_project(&project_dir, &config, None));
+
+            let _ = match result {
+                Ok(result) => window.emit(BUILD_FINISHED_EVENT, BuildFinishedEvent { id, result }),
+                Err(error) => window.emit(BUILD_FAILED_EVENT, BuildFailedEvent { id, error }),
+            };
+
+            let Ok(mut projects) = projects.lock() else {
+                break;
+            };
+            match projects.get_mut(&key).and_then(Option::take) {
+                Some(next) => id = next,
+                None => {
+                    projects.remove(&key);
+                    break;
+                }
+            }
+        }
+    });
+
+    ApiResponse::success(id)
+}
+
+// Error of a live-compile build aborted by a newer change or by watch_compile_stop
+const BUILD_CANCELLED: &str = "Build cancelled";
+
+// How often a running tool is checked for cancellation or a timeout
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+thread_local! {
+    // The live-compile session whose builds run on this thread
+    static LIVE_COMPILE: RefCell<Option<Arc<LiveCompile>>> = const { RefCell::new(None) };
+}
+
+// Size, modification time and content hash of each watched file
+type FileHashes = HashMap<PathBuf, (u64, Option<SystemTime>, String)>;
+
+/// Live-compile sessions keyed by project directory; dropping one ends its thread
+#[derive(Default)]
+pub struct CompileWatchers {
+    sessions: Mutex<HashMap<PathBuf, CompileWatch>>,
+}
+
+struct CompileWatch {
+    _watcher: RecommendedWatcher,
+    live: Arc<LiveCompile>,
+}
+
+impl Drop for CompileWatch {
+    fn drop(&mut self) {
+        // Don't let a stopped session finish a build nobody is waiting for
+        self.live.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// State shared by a live-compile session's watcher, its build thread and the tools it runs
+struct LiveCompile {
+    root: PathBuf,
+    out_dir: PathBuf,
+    // Set to kill the running build's tools
+    cancel: AtomicBool,
+    // Set while the engine runs; engines don't write sources, so a changed one is an edit
+    engine_running: AtomicBool,
+    // The watched files as the current or last build left them; only differences are edits
+    baseline: Mutex<FileHashes>,
+}
+
+impl LiveCompile {
+    // Re-reads the watched files into the baseline; returns whether any of them changed
+    fn rescan(&self) -> bool {
+        let mut baseline = self.baseline.lock().unwrap_or_else(|e| e.into_inner());
+        let current = scan_files(&self.root, &self.out_dir, &baseline);
+        let changed = current.len() != baseline.len()
+            || current
+                .iter()
+                .any(|(path, (_, _, hash))| baseline.get(path).map(|(_, _, old)| old) != Some(hash));
+        *baseline = current;
+        changed
+    }
+
+    // Whether the content of `path` differs from the baseline
+    fn differs(&self, path: &Path) -> bool {
+        let hash = std::fs::read(path).ok().map(|bytes| hash_hex(&bytes));
+        let baseline = self.baseline.lock().unwrap_or_else(|e| e.into_inner());
+        baseline.get(path).map(|(_, _, old)| old) != hash.as_ref()
+    }
+}
+
+// Whether a change to `path` may need a rebuild: files inside the project, but not build
+// outputs or hidden files such as .easypaper/, .git/, editor swap files and our own wrappers
+fn is_watched(root: &Path, out_dir: &Path, path: &Path) -> bool {
+    if path.starts_with(out_dir) {
+        return false;
+    }
+    match path.strip_prefix(root) {
+        Ok(relative) => !relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.')),
+        Err(_) => false,
+    }
+}
+
+// Hashes every watched file, reusing the hash of files whose size and mtime match `previous`
+fn scan_files(root: &Path, out_dir: &Path, previous: &FileHashes) -> FileHashes {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || is_watched(root, out_dir, entry.path()))
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let (size, modified) = (meta.len(), meta.modified().ok());
+            let hash = match previous.get(entry.path()) {
+                Some((old_size, old_modified, hash)) if *old_size == size && *old_modified == modified => hash.clone(),
+                _ => hash_hex(&std::fs::read(entry.path()).ok()?),
+            };
+            Some((entry.into_path(), (size, modified, hash)))
+        })
+        .collect()
+}
+
+/// Rebuild the project whenever its files change, emitting each result as `build://finished`
+/// (or `build://failed` / `build://cancelled`) with a fresh id. Edits within
+/// `compile.min_interval_ms` of each other are coalesced into one build, and a source edited
+/// while the engine runs cancels that build in favour of a new one. Files the build writes
+/// itself, such as hook output, never trigger another build.
+#[tauri::command]
+pub fn watch_compile_start(
+    project_dir: String,
+    window: Window,
+    watchers: State<'_, CompileWatchers>,
+    state: State<'_, ProjectState>,
+) -> ApiResponse<()> {
+    let config = match state.config_for(&project_dir) {
+        Ok(config) => config,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+    if let Some((code, message)) = trust_required(&project_dir, &config) {
+        return ApiResponse::error_with_code(code, message);
+    }
+
+    // Watchers report canonical paths
+    let root = match Path::new(&project_dir).canonicalize() {
+        Ok(root) => root,
+        Err(e) => return ApiResponse::error(format!("Failed to resolve project directory: {}", e)),
+    };
+    let out_dir = config.out_dir(&root.to_string_lossy());
+    let out_dir = out_dir.canonicalize().unwrap_or(out_dir);
+
+    let mut sessions = match watchers.sessions.lock() {
+        Ok(sessions) => sessions,
+        Err(e) => return ApiResponse::error(format!("Failed to access compile watchers: {}", e)),
+    };
+    if sessions.contains_key(&root) {
+        return ApiResponse::success(());
+    }
+
+    let live = Arc::new(LiveCompile {
+        root: root.clone(),
+        out_dir,
+        cancel: AtomicBool::new(false),
+        engine_running: AtomicBool::new(false),
+        baseline: Mutex::new(FileHashes::new()),
+    });
+    let (tx, rx) = mpsc::channel::<()>();
+    let watcher_live = Arc::clone(&live);
+    let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        let live = &watcher_live;
+        let paths: Vec<&PathBuf> = event
+            .paths
+            .iter()
+            .filter(|path| is_watched(&live.root, &live.out_dir, path))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        // Whether anything really changed is settled by a rescan once edits quiet down, but a
+        // source edited under a running engine cancels the build right away
+        if live.engine_running.load(Ordering::Relaxed)
+            && paths.iter().any(|path| is_source_file(path) && live.differs(path))
+        {
+            live.cancel.store(true, Ordering::Relaxed);
+        }
+        let _ = tx.send(());
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => return ApiResponse::error(format!("Failed to create compile watcher: {}", e)),
+    };
+    if let Err(e) = watcher.watch(&root, RecursiveMode::Recursive) {
+        return ApiResponse::error(format!("Failed to watch {}: {}", root.display(), e));
+    }
+
+    let thread_live = Arc::clone(&live);
+    std::thread::spawn(move || {
+        let live = thread_live;
+        LIVE_COMPILE.with(|c| c.replace(Some(Arc::clone(&live))));
+        live.rescan();
+        let mut interval = Duration::from_millis(config.compile.min_interval_ms);
+        // A cancelled build is redone even though the edit that cancelled it is in the baseline by now
+        let mut redo = false;
+
+        loop {
+            // Ends once the watcher, and with it the sender, is dropped
+            if !redo && rx.recv().is_err() {
+                return;
+            }
+            loop {
+                match rx.recv_timeout(interval) {
+                    Ok(()) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            // Armed before the scan so an edit landing in between still cancels the build
+            live.cancel.store(false, Ordering::Relaxed);
+            live.engine_running.store(true, Ordering::Relaxed);
+            if !live.rescan() && !redo {
+                live.engine_running.store(false, Ordering::Relaxed);
+                continue;
+            }
+
+            let id = window.state::<BuildQueue>().next_id.fetch_add(1, Ordering::Relaxed) + 1;
+            // config_for reloads the config when it was edited, so such edits take effect here
+            let result = window
+                .state::<ProjectState>()
+                .config_for(&project_dir)
+                .map_err(|e| format!("Failed to load project config: {}", e))
+                .and_then(|config| {
+                    interval = Duration::from_millis(config.compile.min_interval_ms);
+                    compile_project(&project_dir, &config, None)
+                });
+            live.engine_running.store(false, Ordering::Relaxed);
+            // Whatever the build wrote outside the output directory (_minted-*, ...) is not an edit
+            live.rescan();
+            redo = matches!(&result, Err(error) if error == BUILD_CANCELLED);
+
+            let _ = match result {
+                Ok(result) => window.emit(BUILD_FINISHED_EVENT, BuildFinishedEvent { id, result }),
+                Err(_) if redo => window.emit(BUILD_CANCELLED_EVENT, BuildCancelledEvent { id }),
+                Err(error) => window.emit(BUILD_FAILED_EVENT, BuildFailedEvent { id, error }),
+            };
+        }
+    });
+
+    sessions.insert(
+        root,
+        CompileWatch {
+            _watcher: watcher,
+            live,
+        },
+    );
+    ApiResponse::success(())
+}
+
+/// End a live-compile session, cancelling its running build
+#[tauri::command]
+pub fn watch_compile_stop(project_dir: String, watchers: State<'_, CompileWatchers>) -> ApiResponse<()> {
+    let root = Path::new(&project_dir)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&project_dir));
+
+    match watchers.sessions.lock() {
+        Ok(mut sessions) => {
+            sessions.remove(&root);
+            ApiResponse::success(())
+        }
+        Err(e) => ApiResponse::error(format!("Failed to access compile watchers: {}", e)),
+    }
+}
+
+// Hooks run while the project's build lock is held, so one that hangs must not block builds forever
+const BUILD_HOOK_TIMEOUT: Duration = Duration::from_secs(600);
+
+// Runs a hook through the platform shell in the project directory; returns whether it
+// succeeded and its console output, prefixed with the command
+fn run_hook(project_dir: &str, command: &str) -> Result<(bool, String), String> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+
+    cmd.current_dir(project_dir);
+    // What a hook writes, such as regenerated figures, is part of the build rather than an edit
+    let live = LIVE_COMPILE.with(|c| c.borrow().clone());
+    if let Some(live) = &live {
+        live.engine_running.store(false, Ordering::Relaxed);
+    }
+    let output = run_process_with_timeout(&mut cmd, Some(BUILD_HOOK_TIMEOUT));
+    if let Some(live) = &live {
+        live.rescan();
+        live.engine_running.store(true, Ordering::Relaxed);
+    }
+    let output = output.map_err(|e| match e.kind() {
+        ErrorKind::Interrupted => BUILD_CANCELLED.to_string(),
+        ErrorKind::TimedOut => format!(
+            "Build hook '{}' timed out after {} seconds",
+            command,
+            BUILD_HOOK_TIMEOUT.as_secs()
+        ),
+        _ => format!("Failed to run build hook '{}': {}", command, e),
+    })?;
+
+    let text = format!(
+        "$ {}\n{}{}",
+        command,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok((output.status.success(), text))
+}
+
+// Compile based on engine type and fill in the timing and page count
+pub(crate) fn run_engine(project_dir: &str, config: &ProjectConfig, start: std::time::Instant) -> Result<BuildResult, String> {
+    run_engine_in(project_dir, Path::new(project_dir), config, start)
+}
+
+// run_engine with the engine started in `work_dir`, which holds the main file; search
+// paths, diagnostics and the aux tools still go by the project root
+fn run_engine_in(
+    project_dir: &str,
+    work_dir: &Path,
+    config: &ProjectConfig,
+    start: std::time::Instant,
+) -> Result<BuildResult, String> {
+    config.tex_bin_dir()?;
+
+    // Fall back only when the configured engine isn't installed, never on a failed compile
+    let primary = &config.engine.engine_type;
+    let fallback = match tool_installed(config, primary) {
+        true => None,
+        false => config
+            .engine
+            .fallback_engines
+            .iter()
+            .find(|engine| tool_installed(config, engine)),
+    };
+    let mut fallback_config;
+    let config = match fallback {
+        Some(engine) => {
+            fallback_config = config.clone();
+            fallback_config.engine.engine_type = engine.clone();
+            // Custom arguments are written for the configured engine
+            fallback_config.engine.args.clear();
+            &fallback_config
+        }
+        None => config,
+    };
+
+    let compile = |config: &ProjectConfig| match config.engine.engine_type.as_str() {
+        "tectonic" => compile_with_tectonic(project_dir, work_dir, config),
+        "latexmk" => compile_with_latexmk(project_dir, work_dir, config),
         _ => Err(format!("Unknown engine type: {}", config.engine.engine_type)),
     };
+    let mut build_result = compile(config)?;
+
+    // Glossary and index tools work on the output of a finished pass; another compile picks up theirs
+    if build_result.pdf_path.is_some() {
+        let (tool_errors, changed) = run_aux_tools(project_dir, config);
+        if changed {
+            build_result = compile(config)?;
+        }
+        build_result.errors.extend(tool_errors);
+    }
+
+    if let Some(engine) = fallback {
+        build_result.warnings.push(BuildWarning {
+            file: None,
+            line: None,
+            message: format!("{} is not installed; built with {} instead", primary, engine),
+        });
+    }
+
+    for error in &mut build_result.errors {
+        error.hint = hint_for(&error.message);
+    }
+
+    let suppress = config.suppress_patterns()?;
+    if !suppress.is_empty() {
+        let total = build_result.warnings.len();
+        build_result
+            .warnings
+            .retain(|w| !suppress.iter().any(|re| re.is_match(&w.message)));
+        build_result.suppressed_count = total - build_result.warnings.len();
+    }
+
+    build_result.duration_ms = start.elapsed().as_millis();
+    build_result.page_count = count_pages(&build_result);
+    Ok(build_result)
+}
+
+// Moves (or copies, leaving the originals) the PDF, SyncTeX and log built under `built`'s
+// names to where `config` expects them, and points the result at the new locations
+fn transfer_build_outputs(
+    project_dir: &str,
+    built: &ProjectConfig,
+    config: &ProjectConfig,
+    build_result: &mut BuildResult,
+    copy: bool,
+) -> Result<(), String> {
+    std::fs::create_dir_all(config.out_dir(project_dir))
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    for extension in ["pdf", "synctex.gz", "log"] {
+        let from = built.output_path(project_dir, extension);
+        if from.exists() {
+            let to = config.output_path(project_dir, extension);
+            let transferred = if copy {
+                std::fs::copy(&from, &to).map(|_| ())
+            } else {
+                std::fs::rename(&from, &to)
+            };
+            transferred.map_err(|e| format!("Failed to move {}: {}", from.to_string_lossy(), e))?;
+        }
+    }
+
+    if build_result.pdf_path.is_some() {
+        build_result.pdf_path = Some(config.output_path(project_dir, "pdf").to_string_lossy().to_string());
+    }
+    if build_result.log_path.is_some() {
+        build_result.log_path = Some(config.output_path(project_dir, "log").to_string_lossy().to_string());
+    }
+    Ok(())
+}
+
+// Copies the .aux files (one per \include'd file, in matching subdirectories) and the .bbl
+// that the next run reads back from one output directory to another
+fn copy_rerun_files(from: &Path, to: &Path) -> Result<(), String> {
+    let files = WalkDir::new(from)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "aux" || ext == "bbl"));
+
+    for entry in files {
+        let Ok(relative) = entry.path().strip_prefix(from) else {
+            continue;
+        };
+        let target = to.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create output directory: {}", e))?;
+        }
+        std::fs::copy(entry.path(), &target)
+            .map_err(|e| format!("Failed to copy {}: {}", entry.path().to_string_lossy(), e))?;
+    }
+    Ok(())
+}
+
+// Compiles through a wrapper that sets \includeonly before loading the main file, then moves
+// the outputs to the main file's names. Excluded files keep their page and section numbers
+// from the .aux files an earlier full build left in the output directory.
+fn run_engine_include_only(
+    project_dir: &str,
+    config: &ProjectConfig,
+    only: &[String],
+    start: std::time::Instant,
+) -> Result<BuildResult, String> {
+    if let Some(bad) = only
+        .iter()
+        .find(|name| name.trim().is_empty() || name.contains(['{', '}', ',', '%', '\\']))
+    {
+        return Err(format!("Invalid include_only entry: '{}'", bad));
+    }
+    let names: Vec<&str> = only
+        .iter()
+        .map(|name| name.trim().trim_end_matches(".tex"))
+        .collect();
+
+    let out_dir = config.out_dir(project_dir);
+    let missing_aux: Vec<String> = resolve_document(Path::new(project_dir), &config.main)
+        .map(|doc| doc.includes)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|name| !names.contains(&name.as_str()))
+        .filter(|name| !out_dir.join(format!("{}.aux", name)).exists())
+        .collect();
+
+    let stem = Path::new(&config.main)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| config.main.clone());
+    let wrapper = format!(".{}-includeonly.tex", stem);
+    let wrapper_path = Path::new(project_dir).join(&wrapper);
+    let content = format!(
+        "\\includeonly{{{}}}\n\\input{{{}}}\n",
+        names.join(","),
+        config.main.replace('\\', "/")
+    );
+    std::fs::write(&wrapper_path, content).map_err(|e| format!("Failed to write {}: {}", wrapper, e))?;
+
+    let mut wrapper_config = config.clone();
+    wrapper_config.main = wrapper;
+    // The cached preamble format is dumped from the main file itself
+    wrapper_config.compile.precompile_preamble = false;
+
+    let result = run_engine(project_dir, &wrapper_config, start);
+    let _ = std::fs::remove_file(&wrapper_path);
+    let mut build_result = result?;
+
+    transfer_build_outputs(project_dir, &wrapper_config, config, &mut build_result, false)?;
+
+    if !missing_aux.is_empty() {
+        build_result.warnings.push(BuildWarning {
+            file: None,
+            line: None,
+            message: format!(
+                "No .aux from a full build for {}; their page and section numbers will be off until one runs",
+                missing_aux.join(", ")
+            ),
+        });
+    }
+
+    Ok(build_result)
+}
+
+/// Compiles the editor's unsaved content of the main file, leaving the file on disk untouched.
+/// The outputs are named `.<main>-buffer.*`; diagnostics and SyncTeX refer to the main file.
+#[tauri::command]
+pub fn build_compile_buffer(
+    project_dir: String,
+    main_content: String,
+    state: State<'_, ProjectState>,
+) -> ApiResponse<BuildResult> {
+    let config = match state.config_for(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    if let Some((code, message)) = trust_required(&project_dir, &config) {
+        return ApiResponse::error_with_code(code, message);
+    }
+
+    match compile_buffer(&project_dir, &config, &main_content) {
+        Ok(build_result) => ApiResponse::success(build_result),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+// Builds a hidden copy of the main file next to it, so relative \input paths and line numbers
+// stay the same, then points everything that names the copy back at the main file
+fn compile_buffer(project_dir: &str, config: &ProjectConfig, content: &str) -> Result<BuildResult, String> {
+    let start = std::time::Instant::now();
+    let _lock = lock_project(project_dir)?;
+
+    let main = Path::new(&config.main);
+    let (Some(stem), Some(main_name)) = (main.file_stem(), main.file_name()) else {
+        return Err(format!("Invalid main file: {}", config.main));
+    };
+    let buffer_name = format!(".{}-buffer.tex", stem.to_string_lossy());
+    let buffer = main.with_file_name(&buffer_name).to_string_lossy().replace('\\', "/");
+    let buffer_path = Path::new(project_dir).join(&buffer);
+    std::fs::write(&buffer_path, content).map_err(|e| format!("Failed to write {}: {}", buffer, e))?;
+
+    let mut buffer_config = config.clone();
+    buffer_config.main = buffer.clone();
+    // The cached preamble format is dumped from the saved main file
+    buffer_config.compile.precompile_preamble = false;
+
+    let result = run_engine(project_dir, &buffer_config, start);
+    let _ = std::fs::remove_file(&buffer_path);
+    let mut build_result = result?;
+
+    let main_file = config.main.replace('\\', "/");
+    let files = build_result
+        .errors
+        .iter_mut()
+        .map(|e| &mut e.file)
+        .chain(build_result.warnings.iter_mut().map(|w| &mut w.file))
+        .chain(build_result.undefined_refs.iter_mut().map(|r| &mut r.file));
+    for file in files.filter(|file| file.as_deref() == Some(buffer.as_str())) {
+        *file = Some(main_file.clone());
+    }
+    let main_name = main_name.to_string_lossy();
+    let messages = build_result
+        .errors
+        .iter_mut()
+        .map(|e| &mut e.message)
+        .chain(build_result.warnings.iter_mut().map(|w| &mut w.message));
+    for message in messages {
+        *message = message.replace(&buffer_name, &main_name);
+    }
+
+    let synctex = buffer_config.output_path(project_dir, "synctex.gz");
+    if synctex.exists() {
+        if let Err(e) = rename_synctex_input(&synctex, &buffer_name, &main_name) {
+            build_result.warnings.push(BuildWarning {
+                file: None,
+                line: None,
+                message: format!("SyncTeX may not map back to {}: {}", config.main, e),
+            });
+        }
+    }
+
+    Ok(build_result)
+}
 
-    match result {
-        Ok(mut build_result) => {
-            build_result.duration_ms = start.elapsed().as_millis();
-            ApiResponse::success(build_result)
+// Rewrites the SyncTeX `Input:` records for file `from` to name file `to` in the same directory
+fn rename_synctex_input(synctex: &Path, from: &str, to: &str) -> Result<(), String> {
+    let file = std::fs::File::open(synctex).map_err(|e| format!("Failed to open SyncTeX file: {}", e))?;
+    let mut data = String::new();
+    GzDecoder::new(file)
+        .read_to_string(&mut data)
+        .map_err(|e| format!("Failed to read SyncTeX file: {}", e))?;
+
+    let mut rewritten = String::with_capacity(data.len());
+    for line in data.split_inclusive('\n') {
+        let (text, newline) = match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (line, ""),
+        };
+        match text.strip_suffix(from).filter(|_| text.starts_with("Input:")) {
+            Some(dir) => {
+                rewritten.push_str(dir);
+                rewritten.push_str(to);
+                rewritten.push_str(newline);
+            }
+            None => rewritten.push_str(line),
         }
+    }
+
+    let file = std::fs::File::create(synctex).map_err(|e| format!("Failed to write SyncTeX file: {}", e))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(rewritten.as_bytes())
+        .and_then(|_| encoder.finish().map(|_| ()))
+        .map_err(|e| format!("Failed to write SyncTeX file: {}", e))
+}
+
+/// Compile a `subfiles` document on its own; the PDF lands in the project output directory
+#[tauri::command]
+pub fn build_subfile(project_dir: String, file: String, state: State<'_, ProjectState>) -> ApiResponse<BuildResult> {
+    let start = std::time::Instant::now();
+
+    let config = match state.config_for(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let subfile_path = Path::new(&project_dir).join(&file);
+    if !subfile_path.is_file() {
+        return ApiResponse::error(format!("Subfile '{}' not found", file));
+    }
+    let content = match std::fs::read_to_string(&subfile_path) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read {}: {}", file, e)),
+    };
+    if subfile_parent(&content).is_none() {
+        return ApiResponse::error(format!("{} is not a subfiles document", file));
+    }
+
+    // subfiles resolves `\documentclass[../main.tex]` from the subfile's own directory,
+    // so the engine runs there; everything else still goes by the project root
+    let Some(work_dir) = subfile_path.parent() else {
+        return ApiResponse::error(format!("Invalid subfile path: {}", file));
+    };
+    let mut sub_config = config.clone();
+    sub_config.main = file.clone();
+    sub_config.compile.outdir = config.out_dir(&project_dir).to_string_lossy().to_string();
+    // The cached preamble format belongs to the main document
+    sub_config.compile.precompile_preamble = false;
+    // Hooks belong to full builds, so only shell escape needs the project's trust here
+    sub_config.pre_build = None;
+    sub_config.post_build = None;
+
+    if let Some((code, message)) = trust_required(&project_dir, &sub_config) {
+        return ApiResponse::error_with_code(code, message);
+    }
+
+    let _lock = match lock_project(&project_dir) {
+        Ok(lock) => lock,
+        Err(e) => return ApiResponse::error(e),
+    };
+    match run_engine_in(&project_dir, work_dir, &sub_config, start) {
+        Ok(build_result) => ApiResponse::success(build_result),
         Err(e) => ApiResponse::error(e),
     }
 }
 
-fn compile_with_tectonic(project_dir: &str, config: &ProjectConfig) -> Result<BuildResult, String> {
+fn compile_with_tectonic(project_dir: &str, work_dir: &Path, config: &ProjectConfig) -> Result<BuildResult, String> {
     // Use Tectonic command-line tool (simpler and more stable)
-    let project_path = PathBuf::from(project_dir);
-    let out_dir = project_path.join(&config.compile.outdir);
+    let out_dir = config.out_dir(project_dir);
+
+    // Tectonic manages its own format cache, so there is no preamble fast path
+    let precompiled_preamble = false;
+    let aux_tools = aux_tools(project_dir, config).any();
 
     // Create output directory
     std::fs::create_dir_all(&out_dir)
         .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
-    // Build tectonic command
+    // Tectonic reruns within a single invocation until the .aux settles; a fixed pass count caps it
+    let mut cmd = tectonic_command(project_dir, work_dir, config, &out_dir, aux_tools);
+    if let Some(passes) = config.compile.passes {
+        cmd.arg(format!("--reruns={}", passes.max(1) - 1));
+    }
+    let output = run_tectonic(&mut cmd)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    // Parse output for errors and warnings
+    let (errors, warnings) = parse_tectonic_output(project_dir, work_dir, &stdout, &stderr);
+
+    // Undefined references come from the kept log, falling back to the console output
+    let log_path = config.output_path(project_dir, "log");
+    let undefined_refs = match std::fs::read_to_string(&log_path) {
+        Ok(log) => collect_undefined_refs(&log),
+        Err(_) => collect_undefined_refs(&format!("{}\n{}", stdout, stderr)),
+    };
+
+    // Verify PDF was generated
+    let pdf_path = config.output_path(project_dir, "pdf");
+    let success = output.status.success() && pdf_path.exists();
+
+    Ok(BuildResult {
+        success,
+        pdf_path: if pdf_path.exists() {
+            Some(pdf_path.to_string_lossy().to_string())
+        } else {
+            None
+        },
+        log_path: if log_path.exists() {
+            Some(log_path.to_string_lossy().to_string())
+        } else {
+            None
+        },
+        errors,
+        warnings,
+        suppressed_count: 0,
+        undefined_refs,
+        page_count: None,
+        engine_used: "tectonic".to_string(),
+        precompiled_preamble,
+        hook_output: None,
+        duration_ms: 0,
+    })
+}
+
+fn tectonic_command(project_dir: &str, work_dir: &Path, config: &ProjectConfig, out_dir: &Path, aux_tools: bool) -> Command {
     let mut cmd = Command::new("tectonic");
-    cmd.current_dir(project_dir);
+    cmd.current_dir(work_dir);
+    use_tex_dist(&mut cmd, config);
 
-    // Set output directory
-    cmd.arg(format!("--outdir={}", config.compile.outdir));
+    add_project_search_paths(&mut cmd, project_dir);
+    // Files such as the .gls and .ind that the glossary and index tools write next to the .aux
+    if aux_tools {
+        cmd.arg("-Z").arg(format!("search-path={}", out_dir.to_string_lossy()));
+    }
+
+    // Set output directory; keep the .log for diagnostics and the .aux/.idx/.glo for the aux tools
+    cmd.arg(format!("--outdir={}", out_dir.to_string_lossy()));
+    cmd.arg("--keep-logs");
+    cmd.arg("--keep-intermediates");
 
     // Enable synctex if requested
     if config.compile.synctex {
         cmd.arg("--synctex");
     }
 
+    if config.compile.shell_escape {
+        cmd.args(["-Z", "shell-escape"]);
+    }
+
     // Add the main tex file
-    cmd.arg(&config.main);
+    cmd.arg(engine_main(project_dir, work_dir, config));
 
     // Add any custom engine arguments
     for arg in &config.engine.args {
         cmd.arg(arg);
     }
 
-    // Execute command
-    let output = cmd
-        .output()
-        .map_err(|e| format!("Failed to execute tectonic: {}. Make sure tectonic is installed (brew install tectonic).", e))?;
+    cmd
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+fn run_tectonic(cmd: &mut Command) -> Result<Output, String> {
+    run_process(cmd).map_err(|e| match e.kind() {
+        ErrorKind::Interrupted => BUILD_CANCELLED.to_string(),
+        _ => format!("Failed to execute tectonic: {}. Make sure tectonic is installed (brew install tectonic).", e),
+    })
+}
 
-    // Parse output for errors and warnings
-    let (errors, warnings) = parse_tectonic_output(&stdout, &stderr);
+// Like Command::output, but on a live-compile thread the process is killed as soon as a
+// newer change cancels the build, failing with ErrorKind::Interrupted
+fn run_process(cmd: &mut Command) -> std::io::Result<Output> {
+    run_process_with_timeout(cmd, None)
+}
+
+// run_process that also kills the process once `timeout` has passed, failing with ErrorKind::TimedOut
+fn run_process_with_timeout(cmd: &mut Command, timeout: Option<Duration>) -> std::io::Result<Output> {
+    let live = LIVE_COMPILE.with(|c| c.borrow().clone());
+    if live.is_none() && timeout.is_none() {
+        return cmd.output();
+    }
+    let is_cancelled = || live.as_ref().is_some_and(|live| live.cancel.load(Ordering::Relaxed));
+    let cancelled = || std::io::Error::new(ErrorKind::Interrupted, BUILD_CANCELLED);
+    if is_cancelled() {
+        return Err(cancelled());
+    }
+    let deadline = timeout.map(|timeout| std::time::Instant::now() + timeout);
+
+    // Its own process group, so killing it takes whatever it started along
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Read on their own threads so a chatty engine never blocks on a full pipe
+    let stdout = child.stdout.take().map(read_to_end_thread);
+    let stderr = child.stderr.take().map(read_to_end_thread);
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if is_cancelled() {
+            kill_process_tree(&mut child);
+            return Err(cancelled());
+        }
+        if deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+            kill_process_tree(&mut child);
+            return Err(ErrorKind::TimedOut.into());
+        }
+        std::thread::sleep(CANCEL_POLL_INTERVAL);
+    };
+
+    let collect = |reader: Option<JoinHandle<Vec<u8>>>| reader.and_then(|r| r.join().ok()).unwrap_or_default();
+    Ok(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
 
-    // Verify PDF was generated
-    let pdf_name = config.main.replace(".tex", ".pdf");
-    let pdf_path = out_dir.join(&pdf_name);
-    let success = output.status.success() && pdf_path.exists();
+// Kills the tool together with what it started, e.g. latexmk's pdflatex, so nothing keeps
+// writing to the output directory once the build lock is released
+fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: kill() takes no pointers; the negative pid names the group run_process created
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .output();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
 
-    Ok(BuildResult {
-        success,
-        pdf_path: if pdf_path.exists() {
-            Some(pdf_path.to_string_lossy().to_string())
-        } else {
-            None
-        },
-        log_path: None,
-        errors,
-        warnings,
-        duration_ms: 0,
+fn read_to_end_thread(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
     })
 }
 
-fn compile_with_latexmk(project_dir: &str, config: &ProjectConfig) -> Result<BuildResult, String> {
-    let project_path = PathBuf::from(project_dir);
-    let out_dir = project_path.join(&config.compile.outdir);
+fn compile_with_latexmk(project_dir: &str, work_dir: &Path, config: &ProjectConfig) -> Result<BuildResult, String> {
+    let out_dir = config.out_dir(project_dir);
 
     // Create output directory
     std::fs::create_dir_all(&out_dir)
@@ -123,7 +1251,9 @@ fn compile_with_latexmk(project_dir: &str, config: &ProjectConfig) -> Result<Bui
 
     // Build latexmk command (fallback option, rarely used now)
     let mut cmd = Command::new("latexmk");
-    cmd.current_dir(project_dir);
+    cmd.current_dir(work_dir);
+    use_tex_dist(&mut cmd, config);
+    add_project_search_paths(&mut cmd, project_dir);
     cmd.arg("-pdf");
     cmd.arg("-interaction=nonstopmode");
 
@@ -135,28 +1265,53 @@ fn compile_with_latexmk(project_dir: &str, config: &ProjectConfig) -> Result<Bui
         cmd.arg("-shell-escape");
     }
 
-    cmd.arg(format!("-outdir={}", config.compile.outdir));
-    cmd.arg(&config.main);
+    // latexmk already reruns until stable; a fixed pass count caps it instead
+    if let Some(passes) = config.compile.passes {
+        cmd.arg("-e");
+        cmd.arg(format!("$max_repeat={}", passes.max(1)));
+    }
+
+    // Reuse a precompiled preamble format when enabled; any failure means a normal compile
+    let preamble_format = if config.compile.precompile_preamble {
+        prepare_preamble_format(project_dir, config).ok()
+    } else {
+        None
+    };
+    let precompiled_preamble = preamble_format.is_some();
+    if let Some((fmt_dir, fmt_name)) = &preamble_format {
+        cmd.env("TEXFORMATS", format!("{}{}", fmt_dir.to_string_lossy(), PATH_LIST_SEPARATOR));
+        cmd.arg(format!("-pdflatex=pdflatex -fmt={} %O %S", fmt_name));
+    }
+
+    // Custom arguments such as -lualatex or -xelatex override -pdf
+    for arg in &config.engine.args {
+        cmd.arg(arg);
+    }
+
+    cmd.arg(format!("-outdir={}", out_dir.to_string_lossy()));
+    cmd.arg(engine_main(project_dir, work_dir, config));
 
     // Execute command
-    let output = cmd
-        .output()
-        .map_err(|e| format!("Failed to execute latexmk: {}. Make sure latexmk is installed.", e))?;
+    let output = run_process(&mut cmd).map_err(|e| match e.kind() {
+        ErrorKind::Interrupted => BUILD_CANCELLED.to_string(),
+        _ => format!("Failed to execute latexmk: {}. Make sure latexmk is installed.", e),
+    })?;
 
     let _stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let _stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
     // Parse log file
-    let log_name = config.main.replace(".tex", ".log");
-    let log_path = out_dir.join(&log_name);
+    let log_path = config.output_path(project_dir, "log");
     let (errors, warnings) = if log_path.exists() {
-        parse_latex_log(&log_path.to_string_lossy().to_string())
+        parse_latex_log(project_dir, work_dir, &log_path.to_string_lossy())
     } else {
         (vec![], vec![])
     };
+    let undefined_refs = std::fs::read_to_string(&log_path)
+        .map(|log| collect_undefined_refs(&log))
+        .unwrap_or_default();
 
-    let pdf_name = config.main.replace(".tex", ".pdf");
-    let pdf_path = out_dir.join(&pdf_name);
+    let pdf_path = config.output_path(project_dir, "pdf");
 
     let success = output.status.success() && pdf_path.exists();
 
@@ -174,27 +1329,332 @@ fn compile_with_latexmk(project_dir: &str, config: &ProjectConfig) -> Result<Bui
         },
         errors,
         warnings,
+        suppressed_count: 0,
+        undefined_refs,
+        page_count: None,
+        engine_used: "latexmk".to_string(),
+        precompiled_preamble,
+        hook_output: None,
         duration_ms: 0,
     })
 }
 
-fn parse_tectonic_output(stdout: &str, stderr: &str) -> (Vec<BuildError>, Vec<BuildWarning>) {
+// The main file as the engine started in `work_dir` names it
+fn engine_main(project_dir: &str, work_dir: &Path, config: &ProjectConfig) -> PathBuf {
+    let main = Path::new(project_dir).join(&config.main);
+    match main.strip_prefix(work_dir) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => main,
+    }
+}
+
+// kpathsea path lists are ':'-separated (';' on Windows); a trailing one keeps the default path
+#[cfg(windows)]
+const PATH_LIST_SEPARATOR: &str = ";";
+#[cfg(not(windows))]
+const PATH_LIST_SEPARATOR: &str = ":";
+
+// Whether `name` is installed; with a tex_dist_path only that distribution counts, so builds
+// stay reproducible. Tectonic is never part of one and always comes from PATH.
+fn tool_installed(config: &ProjectConfig, name: &str) -> bool {
+    match config.tex_bin_dir().ok().flatten() {
+        Some(dir) if name != "tectonic" => executable_in(&dir, name).is_some(),
+        _ => find_in_path(name).is_some(),
+    }
+}
+
+// Tools the document needs run between engine passes
+#[derive(Debug, Default)]
+struct AuxTools {
+    glossaries: bool,
+    index: bool,
+}
+
+impl AuxTools {
+    fn any(&self) -> bool {
+        self.glossaries || self.index
+    }
+}
+
+fn glossaries_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\(?:makeglossaries|printglossary|printglossaries|printacronyms)\b").unwrap())
+}
+
+fn index_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\(?:makeindex|printindex)\b").unwrap())
+}
+
+// The config decides where it is set; otherwise the document's source does
+fn aux_tools(project_dir: &str, config: &ProjectConfig) -> AuxTools {
+    let doc = resolve_document(Path::new(project_dir), &config.main).ok();
+    let uses = |re: &Regex| {
+        doc.as_ref()
+            .is_some_and(|doc| doc.source_lines().any(|(_, text)| re.is_match(strip_comment(text))))
+    };
+
+    AuxTools {
+        glossaries: config.compile.glossaries.unwrap_or_else(|| uses(glossaries_regex())),
+        index: uses(index_regex()),
+    }
+}
+
+// Runs the tools the document needs between passes. Returns the errors of the tools that
+// failed and whether they wrote anything the engine has yet to read.
+fn run_aux_tools(project_dir: &str, config: &ProjectConfig) -> (Vec<BuildError>, bool) {
+    let tools = aux_tools(project_dir, config);
+    if !tools.any() {
+        return (vec![], false);
+    }
+
+    let outputs = aux_tool_outputs(project_dir, config, &tools);
+    let hashes = || {
+        outputs
+            .iter()
+            .map(|path| std::fs::read(path).ok().map(|bytes| hash_hex(&bytes)))
+            .collect::<Vec<_>>()
+    };
+    let before = hashes();
+
+    let mut errors = Vec::new();
+    if tools.glossaries {
+        errors.extend(run_makeglossaries(project_dir, config));
+    }
+    if tools.index {
+        errors.extend(run_index_tool(project_dir, config));
+    }
+    (errors, hashes() != before)
+}
+
+fn new_glossary_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\@newglossary\{[^}]*\}\{[^}]*\}\{([^}]*)\}").unwrap())
+}
+
+// Files the aux tools write for the engine: the .ind, and one file per glossary as declared
+// by \@newglossary{name}{log}{out}{in} in the .aux (.gls, .acr, ...)
+fn aux_tool_outputs(project_dir: &str, config: &ProjectConfig, tools: &AuxTools) -> Vec<PathBuf> {
+    let mut extensions = Vec::new();
+    if tools.index {
+        extensions.push("ind".to_string());
+    }
+    if tools.glossaries {
+        let aux = std::fs::read_to_string(config.output_path(project_dir, "aux")).unwrap_or_default();
+        let declared: Vec<String> = new_glossary_regex()
+            .captures_iter(&aux)
+            .map(|caps| caps[1].to_string())
+            .collect();
+        match declared.is_empty() {
+            true => extensions.push("gls".to_string()),
+            false => extensions.extend(declared),
+        }
+    }
+    extensions
+        .iter()
+        .map(|extension| config.output_path(project_dir, extension))
+        .collect()
+}
+
+// Turns the build's .idx into the .ind that \printindex reads
+fn run_index_tool(project_dir: &str, config: &ProjectConfig) -> Vec<BuildError> {
+    let idx = config.output_path(project_dir, "idx");
+    let (Some(dir), Some(stem)) = (idx.parent(), idx.file_stem()) else {
+        return vec![];
+    };
+    // No .idx means the document never reached \makeindex
+    if !idx.exists() {
+        return vec![];
+    }
+
+    let tool = config.compile.index_tool.as_deref().unwrap_or("makeindex");
+    if !tool_installed(config, tool) {
+        return vec![aux_tool_error(format!("{} is not installed; the index will be empty", tool))];
+    }
+
+    let stem = stem.to_string_lossy();
+    let mut cmd = Command::new(tool);
+    use_tex_dist(&mut cmd, config);
+    cmd.current_dir(dir);
+    if tool == "xindy" {
+        cmd.args(["-M", "texindy", "-C", "utf8", "-o"]).arg(format!("{}.ind", stem));
+    }
+    cmd.arg(format!("{}.idx", stem));
+
+    let output = match run_process(&mut cmd) {
+        Ok(output) => output,
+        Err(e) => return vec![aux_tool_error(format!("Failed to run {}: {}", tool, e))],
+    };
+
+    // makeindex skips entries it can't parse and still succeeds; its .ilg transcript says why
+    let mut errors: Vec<BuildError> = std::fs::read_to_string(dir.join(format!("{}.ilg", stem)))
+        .map(|ilg| makeindex_errors(&ilg))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|message| aux_tool_error(format!("{}: {}", tool, message)))
+        .collect();
+    if !output.status.success() && errors.is_empty() {
+        errors.push(aux_tool_error(format!("{} failed: {}", tool, failure_reason(&output))));
+    }
+    errors
+}
+
+// "!! Input index error (file = main.idx, line = 2):" records and the explanation on the next line
+fn makeindex_errors(ilg: &str) -> Vec<String> {
+    let lines: Vec<&str> = ilg.lines().collect();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with("!!"))
+        .map(|(i, line)| {
+            let detail = lines.get(i + 1).map(|l| l.trim().trim_start_matches("-- ")).unwrap_or("");
+            format!("{} {}", line.trim_start_matches("!!").trim(), detail).trim().to_string()
+        })
+        .collect()
+}
+
+fn run_makeglossaries(project_dir: &str, config: &ProjectConfig) -> Option<BuildError> {
+    let aux = config.output_path(project_dir, "aux");
+    let (Some(dir), Some(stem)) = (aux.parent(), aux.file_stem()) else {
+        return None;
+    };
+
+    // makeglossaries-lite needs only Lua where the Perl script can't run
+    let Some(tool) = ["makeglossaries", "makeglossaries-lite"]
+        .into_iter()
+        .find(|tool| tool_installed(config, tool))
+    else {
+        return Some(aux_tool_error("makeglossaries is not installed; the glossaries will be empty".to_string()));
+    };
+
+    let mut cmd = Command::new(tool);
+    use_tex_dist(&mut cmd, config);
+    match run_process(cmd.current_dir(dir).arg(stem)) {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(aux_tool_error(format!("{} failed: {}", tool, failure_reason(&output)))),
+        Err(e) => Some(aux_tool_error(format!("Failed to run {}: {}", tool, e))),
+    }
+}
+
+fn aux_tool_error(message: String) -> BuildError {
+    BuildError {
+        file: None,
+        line: None,
+        message,
+        hint: None,
+    }
+}
+
+// The line of a failed tool's output that best explains the failure
+fn failure_reason(output: &std::process::Output) -> String {
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines
+        .iter()
+        .find(|line| {
+            let lower = line.to_lowercase();
+            lower.contains("error") || lower.contains("fail")
+        })
+        .or(lines.last())
+        .map(|line| line.to_string())
+        .unwrap_or_else(|| format!("exited with {}", output.status))
+}
+
+/// Runs the engine from `engine.tex_dist_path` when set; the bibtex/biber/makeindex runs it
+/// starts inherit the same PATH
+fn use_tex_dist(cmd: &mut Command, config: &ProjectConfig) {
+    let bin_dir = config.tex_bin_dir().ok().flatten();
+    if let Some(path) = bin_dir.as_deref().and_then(path_with_dir) {
+        cmd.env("PATH", path);
+    }
+}
+
+/// Puts the project root in front of the bibtex/biber search paths so local .bst/.bib files resolve
+fn add_project_search_paths(cmd: &mut Command, project_dir: &str) {
+    let root = std::path::absolute(project_dir).unwrap_or_else(|_| PathBuf::from(project_dir));
+    for var in ["BSTINPUTS", "BIBINPUTS"] {
+        let existing = std::env::var(var).unwrap_or_default();
+        cmd.env(var, format!("{}{}{}", root.to_string_lossy(), PATH_LIST_SEPARATOR, existing));
+    }
+}
+
+// Dumps the main file's preamble into a pdflatex format with mylatexformat, keyed by its hash.
+// Returns the format directory and name.
+fn prepare_preamble_format(project_dir: &str, config: &ProjectConfig) -> Result<(PathBuf, String), String> {
+    let main_path = PathBuf::from(project_dir).join(&config.main);
+    let content = std::fs::read_to_string(&main_path)
+        .map_err(|e| format!("Failed to read {}: {}", config.main, e))?;
+    let preamble = extract_preamble(&content).ok_or("Main file has no \\begin{document}")?;
+
+    let hash = hash_hex(preamble.as_bytes());
+    let fmt_name = format!("preamble-{}", &hash[..16]);
+    let fmt_dir = std::path::absolute(cache_dir(project_dir).join("preamble"))
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+
+    if fmt_dir.join(format!("{}.fmt", fmt_name)).exists() {
+        return Ok((fmt_dir, fmt_name));
+    }
+
+    // Formats for older preambles are never reused
+    let _ = std::fs::remove_dir_all(&fmt_dir);
+    std::fs::create_dir_all(&fmt_dir)
+        .map_err(|e| format!("Failed to create preamble cache: {}", e))?;
+
+    let mut cmd = Command::new("pdflatex");
+    use_tex_dist(&mut cmd, config);
+    cmd.current_dir(project_dir)
+        .arg("-ini")
+        .arg("-interaction=nonstopmode")
+        .arg(format!("-jobname={}", fmt_name))
+        .arg(format!("-output-directory={}", fmt_dir.to_string_lossy()))
+        .arg("&pdflatex")
+        .arg("mylatexformat.ltx")
+        .arg(&config.main);
+    let output = run_process(&mut cmd).map_err(|e| format!("Failed to execute pdflatex: {}", e))?;
+
+    if !output.status.success() || !fmt_dir.join(format!("{}.fmt", fmt_name)).exists() {
+        return Err("Failed to precompile the preamble".to_string());
+    }
+
+    Ok((fmt_dir, fmt_name))
+}
+
+fn tectonic_location_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // e.g. "error: sections/intro.tex:12: Undefined control sequence"
+    RE.get_or_init(|| Regex::new(r"(?i)(?:error|warning):\s*(.+?\.[a-z]+):(\d+):").unwrap())
+}
+
+fn parse_tectonic_output(project_dir: &str, work_dir: &Path, stdout: &str, stderr: &str) -> (Vec<BuildError>, Vec<BuildWarning>) {
     let mut errors = Vec::new();
     let mut warnings = Vec::new();
 
     let combined = format!("{}\n{}", stdout, stderr);
 
     for line in combined.lines() {
+        let (file, line_num) = match tectonic_location_regex().captures(line) {
+            Some(caps) => (Some(project_relative_path(project_dir, work_dir, &caps[1])), caps[2].parse().ok()),
+            None => (None, None),
+        };
+
         if line.contains("error:") || line.contains("Error:") {
             errors.push(BuildError {
-                file: None,
-                line: None,
+                file,
+                line: line_num,
                 message: line.to_string(),
+                hint: None,
             });
+        } else if is_undefined_ref_warning(line) {
+            // Reported separately through `undefined_refs`
+            continue;
         } else if line.contains("warning:") || line.contains("Warning:") {
             warnings.push(BuildWarning {
-                file: None,
-                line: None,
+                file,
+                line: line_num,
                 message: line.to_string(),
             });
         }
@@ -203,7 +1663,7 @@ fn parse_tectonic_output(stdout: &str, stderr: &str) -> (Vec<BuildError>, Vec<Bu
     (errors, warnings)
 }
 
-fn parse_latex_log(log_path: &str) -> (Vec<BuildError>, Vec<BuildWarning>) {
+fn parse_latex_log(project_dir: &str, work_dir: &Path, log_path: &str) -> (Vec<BuildError>, Vec<BuildWarning>) {
     let mut errors = Vec::new();
     let mut warnings = Vec::new();
 
@@ -219,11 +1679,16 @@ fn parse_latex_log(log_path: &str) -> (Vec<BuildError>, Vec<BuildWarning>) {
                 let (file, line_num) = extract_file_line(&lines, i);
 
                 errors.push(BuildError {
-                    file,
+                    file: file.map(|f| project_relative_path(project_dir, work_dir, &f)),
                     line: line_num,
                     message,
+                    hint: None,
                 });
             }
+            // Undefined references are reported separately through `undefined_refs`
+            else if is_undefined_ref_warning(line) {
+                continue;
+            }
             // Warning detection
             else if line.contains("Warning:") {
                 warnings.push(BuildWarning {
@@ -245,12 +1710,10 @@ fn extract_file_line(lines: &[&str], error_idx: usize) -> (Option<String>, Optio
 
         // Pattern: ./file.tex:123
         if let Some(pos) = line.find(".tex:") {
-            let parts: Vec<&str> = line[..pos+4].split(':').collect();
-            if parts.len() >= 2 {
-                let file = parts[0].trim_start_matches("./").to_string();
-                if let Ok(num) = parts[1].parse::<u32>() {
-                    return (Some(file), Some(num));
-                }
+            let file = &line[..pos + 4];
+            let digits: String = line[pos + 5..].chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(num) = digits.parse::<u32>() {
+                return (Some(file.to_string()), Some(num));
             }
         }
     }
@@ -258,21 +1721,492 @@ fn extract_file_line(lines: &[&str], error_idx: usize) -> (Option<String>, Optio
     (None, None)
 }
 
+/// Engine-reported paths (`./a.tex`, `sections/b.tex`, `/abs/project/c.tex`), relative ones
+/// taken from the engine's `work_dir`, as the project-relative, forward-slash form the editor
+/// uses for its tabs
+fn project_relative_path(project_dir: &str, work_dir: &Path, file: &str) -> String {
+    let file = file.trim();
+    let root = Path::new(project_dir);
+    let path = Path::new(file);
+    let joined = if path.is_absolute() { path.to_path_buf() } else { work_dir.join(path) };
+
+    let relative = match (joined.canonicalize(), root.canonicalize()) {
+        (Ok(full), Ok(root)) => full.strip_prefix(&root).map(Path::to_path_buf).ok(),
+        _ => None,
+    };
+
+    match relative {
+        Some(relative) => relative.to_string_lossy().replace('\\', "/"),
+        // Outside the project or not on disk: keep the engine's path, minus a leading ./
+        None => file.trim_start_matches("./").replace('\\', "/"),
+    }
+}
+
+// Page count from the log's "Output written on ... (N pages" line, falling back to the PDF itself
+fn count_pages(result: &BuildResult) -> Option<u32> {
+    static LOG_RE: OnceLock<Regex> = OnceLock::new();
+    static PDF_RE: OnceLock<regex::bytes::Regex> = OnceLock::new();
+
+    if let Some(log) = result.log_path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) {
+        // TeX wraps long log lines, so search with the line breaks removed
+        let unwrapped = log.replace(['\r', '\n'], "");
+        let re = LOG_RE.get_or_init(|| Regex::new(r"Output written on .*?\((\d+) pages?").unwrap());
+        if let Some(caps) = re.captures(&unwrapped) {
+            return caps[1].parse().ok();
+        }
+    }
+
+    // Only works for PDFs whose page objects aren't inside compressed object streams
+    let bytes = std::fs::read(result.pdf_path.as_ref()?).ok()?;
+    let re = PDF_RE.get_or_init(|| regex::bytes::Regex::new(r"/Type\s*/Page[^s]").unwrap());
+    match re.find_iter(&bytes).count() {
+        0 => None,
+        n => Some(n as u32),
+    }
+}
+
+fn history_path(project_dir: &str) -> PathBuf {
+    cache_dir(project_dir).join("build_history.json")
+}
+
+fn record_build_stat(project_dir: &str, result: &BuildResult) -> Result<(), String> {
+    let path = history_path(project_dir);
+    let mut history: Vec<BuildStat> = read_json(&path)?.unwrap_or_default();
+
+    history.push(BuildStat {
+        timestamp: unix_timestamp(),
+        duration_ms: result.duration_ms,
+        page_count: result.page_count,
+        error_count: result.errors.len(),
+        warning_count: result.warnings.len(),
+    });
+    if history.len() > MAX_BUILD_HISTORY {
+        history.drain(..history.len() - MAX_BUILD_HISTORY);
+    }
+
+    write_json(&path, &history)
+}
+
+/// Statistics of past successful builds, oldest first
+#[tauri::command]
+pub fn build_history(project_dir: String) -> ApiResponse<Vec<BuildStat>> {
+    match read_json(&history_path(&project_dir)) {
+        Ok(history) => ApiResponse::success(history.unwrap_or_default()),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+fn diagnostics_path(project_dir: &str) -> PathBuf {
+    cache_dir(project_dir).join("diagnostics.json")
+}
+
+// Groups the build's errors and warnings by source file; ones without a location go
+// to the main file
+fn save_diagnostics(project_dir: &str, config: &ProjectConfig, result: &BuildResult) -> Result<(), String> {
+    let mut by_file: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+    let errors = result.errors.iter().map(|e| (&e.file, "error", e.line, &e.message, e.hint.clone()));
+    let warnings = result.warnings.iter().map(|w| (&w.file, "warning", w.line, &w.message, None));
+
+    for (file, severity, line, message, hint) in errors.chain(warnings) {
+        let file = file.clone().unwrap_or_else(|| config.main.clone());
+        by_file.entry(file).or_default().push(Diagnostic {
+            severity: severity.to_string(),
+            line,
+            message: message.clone(),
+            hint,
+        });
+    }
+
+    write_json(&diagnostics_path(project_dir), &by_file)
+}
+
+/// Errors and warnings of the project's last build, grouped by project-relative file
+#[tauri::command]
+pub fn build_diagnostics_by_file(project_dir: String) -> ApiResponse<HashMap<String, Vec<Diagnostic>>> {
+    match read_json(&diagnostics_path(&project_dir)) {
+        Ok(diagnostics) => ApiResponse::success(diagnostics.unwrap_or_default()),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+/// The last build's diagnostics for CI, as "json" (the `build_diagnostics_by_file` map) or
+/// "sarif" (SARIF 2.1.0, e.g. for GitHub code scanning). Builds first if there is no last build.
+#[tauri::command]
+pub fn build_report(project_dir: String, format: String, state: State<'_, ProjectState>) -> ApiResponse<String> {
+    if format != "json" && format != "sarif" {
+        return ApiResponse::error(format!("Unknown report format: {} (expected json or sarif)", format));
+    }
+
+    let path = diagnostics_path(&project_dir);
+    let mut diagnostics: Option<BTreeMap<String, Vec<Diagnostic>>> = match read_json(&path) {
+        Ok(diagnostics) => diagnostics,
+        Err(e) => return ApiResponse::error(e),
+    };
+    if diagnostics.is_none() {
+        let config = match state.config_for(&project_dir) {
+            Ok(cfg) => cfg,
+            Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+        };
+        if let Some((code, message)) = trust_required(&project_dir, &config) {
+            return ApiResponse::error_with_code(code, message);
+        }
+        if let Err(e) = compile_project(&project_dir, &config, None) {
+            return ApiResponse::error(e);
+        }
+        diagnostics = match read_json(&path) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => return ApiResponse::error(e),
+        };
+    }
+    let diagnostics = diagnostics.unwrap_or_default();
+
+    let report = match format.as_str() {
+        "sarif" => sarif_report(&diagnostics),
+        _ => serde_json::to_value(&diagnostics).unwrap_or_default(),
+    };
+    match serde_json::to_string_pretty(&report) {
+        Ok(report) => ApiResponse::success(report),
+        Err(e) => ApiResponse::error(format!("Failed to serialize build report: {}", e)),
+    }
+}
+
+// One SARIF run with a result per diagnostic; file URIs are relative to the project root
+fn sarif_report(diagnostics: &BTreeMap<String, Vec<Diagnostic>>) -> serde_json::Value {
+    let mut results = Vec::new();
+    for (file, file_diagnostics) in diagnostics {
+        for diagnostic in file_diagnostics {
+            let text = match &diagnostic.hint {
+                Some(hint) => format!("{}\n{}", diagnostic.message, hint),
+                None => diagnostic.message.clone(),
+            };
+            // Files outside the project (e.g. a package's .sty) keep their absolute path
+            let artifact = if Path::new(file).is_absolute() {
+                let path = file.replace('\\', "/");
+                let path = sarif_uri(path.strip_prefix('/').unwrap_or(&path)).replacen("%3A", ":", 1);
+                json!({ "uri": format!("file:///{}", path) })
+            } else {
+                json!({ "uri": sarif_uri(file), "uriBaseId": "%SRCROOT%" })
+            };
+            let mut location = json!({ "artifactLocation": artifact });
+            if let Some(line) = diagnostic.line.filter(|line| *line > 0) {
+                location["region"] = json!({ "startLine": line });
+            }
+            results.push(json!({
+                "ruleId": format!("latex-{}", diagnostic.severity),
+                "level": diagnostic.severity,
+                "message": { "text": text },
+                "locations": [{ "physicalLocation": location }],
+            }));
+        }
+    }
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "EasyPaper",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [
+                        { "id": "latex-error", "shortDescription": { "text": "LaTeX error" } },
+                        { "id": "latex-warning", "shortDescription": { "text": "LaTeX warning" } },
+                    ],
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+// Percent-encodes a path for use in a URI
+fn sarif_uri(path: &str) -> String {
+    let mut uri = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+fn snapshot_path(project_dir: &str) -> PathBuf {
+    cache_dir(project_dir).join("build_snapshot.json")
+}
+
+fn save_build_snapshot(project_dir: &str, config: &ProjectConfig) -> Result<(), String> {
+    let path = snapshot_path(project_dir);
+    let previous: Option<SourceSnapshot> = read_json(&path)?;
+    let snapshot = snapshot_sources(project_dir, &config.out_dir(project_dir), previous.as_ref());
+    write_json(&path, &snapshot)
+}
+
+/// Source files changed since the last successful build (every source file if there was none)
+#[tauri::command]
+pub fn build_changed_files(project_dir: String, state: State<'_, ProjectState>) -> ApiResponse<Vec<String>> {
+    let config = match state.config_for(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let previous: SourceSnapshot = match read_json(&snapshot_path(&project_dir)) {
+        Ok(previous) => previous.unwrap_or_default(),
+        Err(e) => return ApiResponse::error(e),
+    };
+    let current = snapshot_sources(&project_dir, &config.out_dir(&project_dir), Some(&previous));
+
+    ApiResponse::success(changed_files(&previous, &current))
+}
+
+fn undefined_ref_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        // Older kernels quote keys as `key', newer ones as 'key'
+        Regex::new(r"(?:(\S+\.tex):(\d+):.*)?(Reference|Citation) [`']([^']+)' on page (\d+) undefined(?: on input line (\d+))?").unwrap()
+    })
+}
+
+fn is_undefined_ref_warning(line: &str) -> bool {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(Reference|Citation) [`'][^']+' on page").unwrap())
+        .is_match(line)
+}
+
+fn collect_undefined_refs(text: &str) -> Vec<UndefinedRef> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut refs: Vec<UndefinedRef> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if !is_undefined_ref_warning(line) {
+            continue;
+        }
+
+        // TeX wraps log lines at 79 characters, so the message may continue on the next line
+        let mut message = line.to_string();
+        if !message.contains("input line") {
+            if let Some(next) = lines.get(i + 1) {
+                message.push_str(next);
+            }
+        }
+
+        if let Some(caps) = undefined_ref_regex().captures(&message) {
+            let undefined = UndefinedRef {
+                kind: if &caps[3] == "Reference" { "reference" } else { "citation" }.to_string(),
+                key: caps[4].to_string(),
+                file: caps.get(1).map(|m| m.as_str().trim_start_matches("./").to_string()),
+                line: caps
+                    .get(6)
+                    .or(caps.get(2))
+                    .and_then(|m| m.as_str().parse().ok()),
+                page: caps[5].parse().ok(),
+            };
+            if !refs.contains(&undefined) {
+                refs.push(undefined);
+            }
+        }
+    }
+
+    refs
+}
+
+/// Report undefined references and citations from the last build's log
+#[tauri::command]
+pub fn build_check_refs(project_dir: String, state: State<'_, ProjectState>) -> ApiResponse<Vec<UndefinedRef>> {
+    let config = match state.config_for(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let log_path = config.output_path(&project_dir, "log");
+
+    match std::fs::read_to_string(&log_path) {
+        Ok(log) => ApiResponse::success(collect_undefined_refs(&log)),
+        Err(_) => ApiResponse::error(format!(
+            "No build log found at {}. Compile the project first.",
+            log_path.to_string_lossy()
+        )),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanResult {
+    pub files_removed: usize,
+    pub bytes_freed: u64,
+    pub paths: Vec<String>,
+    pub dry_run: bool,
+}
+
+// Auxiliary files LaTeX tools may leave next to the sources
+const ARTIFACT_SUFFIXES: &[&str] = &[
+    ".aux", ".bbl", ".blg", ".log", ".out", ".toc", ".lof", ".lot", ".fls", ".fdb_latexmk",
+    ".synctex.gz", ".synctex", ".xdv", ".dvi", ".nav", ".snm", ".vrb", ".bcf", ".run.xml",
+    ".idx", ".ind", ".ilg", ".glo", ".gls", ".glg", ".ist", ".acn", ".acr", ".alg",
+];
+
+// Source files that must never be treated as artifacts
+const PROTECTED_SUFFIXES: &[&str] = &[".tex", ".bib", ".sty", ".cls", ".bst"];
+
 #[tauri::command]
-pub fn build_clean(project_dir: String) -> ApiResponse<()> {
-    let config = match ProjectConfig::load(&project_dir) {
+pub fn build_clean(
+    project_dir: String,
+    deep: Option<bool>,
+    dry: Option<bool>,
+    state: State<'_, ProjectState>,
+) -> ApiResponse<CleanResult> {
+    let config = match state.config_for(&project_dir) {
         Ok(cfg) => cfg,
         Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
     };
 
-    let out_dir = PathBuf::from(&project_dir).join(&config.compile.outdir);
+    let dry_run = dry.unwrap_or(false);
+    let project_path = PathBuf::from(&project_dir);
+    let out_dir = config.out_dir(&project_dir);
+    let mut targets: Vec<(PathBuf, u64)> = Vec::new();
 
     if out_dir.exists() {
-        match std::fs::remove_dir_all(&out_dir) {
-            Ok(_) => ApiResponse::success(()),
-            Err(e) => ApiResponse::error(format!("Failed to clean output directory: {}", e)),
+        // An outdir of "." would otherwise wipe the whole project
+        let same_as_project = match (out_dir.canonicalize(), project_path.canonicalize()) {
+            (Ok(out), Ok(project)) => project.starts_with(&out),
+            _ => false,
+        };
+        if same_as_project {
+            return ApiResponse::error("Output directory contains the project; refusing to clean it".to_string());
         }
-    } else {
-        ApiResponse::success(())
+
+        for entry in WalkDir::new(&out_dir).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                targets.push((entry.path().to_path_buf(), size));
+            }
+        }
+    }
+
+    let mut root_artifacts = Vec::new();
+    if deep.unwrap_or(false) {
+        if let Ok(entries) = std::fs::read_dir(&project_path) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_artifact = ARTIFACT_SUFFIXES.iter().any(|s| name.ends_with(s))
+                    && !PROTECTED_SUFFIXES.iter().any(|s| name.ends_with(s));
+                if is_artifact && entry.path().is_file() {
+                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    root_artifacts.push(entry.path());
+                    targets.push((entry.path(), size));
+                }
+            }
+        }
+    }
+
+    if !dry_run {
+        if out_dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&out_dir) {
+                return ApiResponse::error(format!("Failed to clean output directory: {}", e));
+            }
+        }
+        for path in &root_artifacts {
+            if let Err(e) = std::fs::remove_file(path) {
+                return ApiResponse::error(format!("Failed to remove {}: {}", path.to_string_lossy(), e));
+            }
+        }
+    }
+
+    ApiResponse::success(CleanResult {
+        files_removed: targets.len(),
+        bytes_freed: targets.iter().map(|(_, size)| size).sum(),
+        paths: targets
+            .iter()
+            .map(|(path, _)| {
+                path.strip_prefix(&project_path)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect(),
+        dry_run,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_paths_find_a_project_local_bst() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("acl.bst"), "ENTRY {} {} {}").unwrap();
+
+        let mut cmd = Command::new("bibtex");
+        add_project_search_paths(&mut cmd, &project.path().to_string_lossy());
+
+        for var in ["BSTINPUTS", "BIBINPUTS"] {
+            let value = cmd
+                .get_envs()
+                .find(|(key, _)| *key == var)
+                .and_then(|(_, value)| value)
+                .unwrap_or_else(|| panic!("{} not set", var))
+                .to_string_lossy()
+                .to_string();
+            let first = value.split(PATH_LIST_SEPARATOR).next().unwrap();
+            assert!(Path::new(first).is_absolute());
+            assert!(Path::new(first).join("acl.bst").is_file(), "{} = {}", var, value);
+        }
+    }
+
+    #[test]
+    fn file_line_from_the_lines_before_an_error() {
+        let lines = [
+            "(./main.tex (./sections/intro.tex",
+            "./sections/intro.tex:12: Undefined control sequence.",
+            "l.12 \\foo",
+            "!  ==> Fatal error occurred",
+        ];
+        assert_eq!(extract_file_line(&lines, 3), (Some("./sections/intro.tex".to_string()), Some(12)));
+
+        let lines = ["/home/me/paper/main.tex:3: Missing $ inserted.", "! Emergency stop."];
+        assert_eq!(extract_file_line(&lines, 1), (Some("/home/me/paper/main.tex".to_string()), Some(3)));
+
+        let lines = ["Overfull \\hbox", "! Emergency stop."];
+        assert_eq!(extract_file_line(&lines, 1), (None, None));
+    }
+
+    #[test]
+    fn engine_paths_become_project_relative() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::create_dir(project.path().join("sections")).unwrap();
+        std::fs::write(project.path().join("main.tex"), "").unwrap();
+        std::fs::write(project.path().join("sections").join("intro.tex"), "").unwrap();
+        let dir = project.path().to_string_lossy().to_string();
+
+        assert_eq!(project_relative_path(&dir, project.path(), "./main.tex"), "main.tex");
+        assert_eq!(project_relative_path(&dir, project.path(), "sections/intro.tex"), "sections/intro.tex");
+        assert_eq!(project_relative_path(&dir, project.path(), "./sections/intro.tex"), "sections/intro.tex");
+        let absolute = project.path().join("sections").join("intro.tex");
+        assert_eq!(project_relative_path(&dir, project.path(), &absolute.to_string_lossy()), "sections/intro.tex");
+    }
+
+    #[test]
+    fn unknown_paths_keep_the_engine_form() {
+        let project = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("shared.tex"), "").unwrap();
+        let dir = project.path().to_string_lossy().to_string();
+
+        assert_eq!(project_relative_path(&dir, project.path(), "./deleted.tex"), "deleted.tex");
+        let shared = outside.path().join("shared.tex").to_string_lossy().replace('\\', "/");
+        assert_eq!(project_relative_path(&dir, project.path(), &shared), shared);
+    }
+
+    #[test]
+    fn subfile_paths_are_taken_from_the_engine_directory() {
+        let project = tempfile::tempdir().unwrap();
+        let chapters = project.path().join("chapters");
+        std::fs::create_dir(&chapters).unwrap();
+        std::fs::write(project.path().join("main.tex"), "").unwrap();
+        std::fs::write(chapters.join("intro.tex"), "").unwrap();
+        let dir = project.path().to_string_lossy().to_string();
+
+        assert_eq!(project_relative_path(&dir, &chapters, "./intro.tex"), "chapters/intro.tex");
+        assert_eq!(project_relative_path(&dir, &chapters, "../main.tex"), "main.tex");
     }
 }
@@ -0,0 +1,93 @@
+use crate::svc_file::ApiResponse;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: String, // "context", "added" or "removed"
+    pub content: String,
+    /// 1-based line numbers; `None` on the side the line is absent from
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+// Unchanged lines shown around each change, as in `diff -u`
+const CONTEXT_LINES: usize = 3;
+
+pub(crate) fn diff_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut hunks = Vec::new();
+
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => "context",
+                    ChangeTag::Insert => "added",
+                    ChangeTag::Delete => "removed",
+                };
+                lines.push(DiffLine {
+                    kind: kind.to_string(),
+                    content: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                    old_line: change.old_index().map(|i| i as u32 + 1),
+                    new_line: change.new_index().map(|i| i as u32 + 1),
+                });
+            }
+        }
+
+        hunks.push(DiffHunk {
+            old_start: old_range.start as u32 + 1,
+            old_lines: old_range.len() as u32,
+            new_start: new_range.start as u32 + 1,
+            new_lines: new_range.len() as u32,
+            lines,
+        });
+    }
+
+    hunks
+}
+
+fn read_text(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Failed to read file '{}': {}", path, e))
+}
+
+/// Line diff from `path_a` to `path_b`
+#[tauri::command]
+pub fn file_diff(path_a: String, path_b: String) -> ApiResponse<Vec<DiffHunk>> {
+    let old = match read_text(&path_a) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(e),
+    };
+    let new = match read_text(&path_b) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    ApiResponse::success(diff_hunks(&old, &new))
+}
+
+/// Line diff from an unsaved editor buffer to the file on disk
+#[tauri::command]
+pub fn file_diff_content(content: String, path: String) -> ApiResponse<Vec<DiffHunk>> {
+    match read_text(&path) {
+        Ok(on_disk) => ApiResponse::success(diff_hunks(&content, &on_disk)),
+        Err(e) => ApiResponse::error(e),
+    }
+}
@@ -0,0 +1,644 @@
+use crate::document::{resolve_document, strip_comment, SourceLocation};
+use crate::project::ProjectConfig;
+use crate::svc_bib::{cited_keys, project_bib_files, uncited_entries, UnusedBibEntry};
+use crate::svc_file::ApiResponse;
+use crate::svc_spell::ProseScanner;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FigureRef {
+    pub file: String,
+    pub line: u32,
+    pub command_line: String,
+    pub path: String,
+    pub resolved_path: String,
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlineItem {
+    pub title: String,
+    pub level: u32,
+    pub numbered: bool,
+    pub file: String,
+    pub line: u32,
+    pub children: Vec<OutlineItem>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectStats {
+    pub files: usize,
+    pub sections: usize,
+    pub figures: usize,
+    pub tables: usize,
+    /// Citation commands, and the distinct keys they cite
+    pub citations: usize,
+    pub cited_keys: usize,
+    pub equations: usize,
+    /// Prose words in the document body, excluding commands, math and single letters
+    pub words: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocMeta {
+    pub title: Option<String>,
+    /// Author names without affiliations; empty when none were found
+    pub authors: Vec<String>,
+    pub r#abstract: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroDef {
+    /// `\foo` for commands and operators, the bare name for environments
+    pub name: String,
+    /// The defining command, e.g. "newcommand", "DeclareMathOperator" or "newenvironment"
+    pub definition: String,
+    pub arity: u32,
+    /// Whether the first argument is optional with a default value
+    pub has_default: bool,
+    pub file: String,
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnusedReport {
+    /// Images in the graphics directories that no \includegraphics resolves to
+    pub figures: Vec<String>,
+    /// Entries of the document's .bib files that are never cited
+    pub bib_entries: Vec<UnusedBibEntry>,
+    /// .bib files in the project that the document never names
+    pub bib_files: Vec<String>,
+}
+
+// Sectioning commands and their nesting level
+const SECTION_LEVELS: &[(&str, u32)] = &[
+    ("chapter", 0),
+    ("section", 1),
+    ("subsection", 2),
+    ("subsubsection", 3),
+    ("paragraph", 4),
+];
+
+// Extensions graphicx tries, in order, when \includegraphics omits one
+const GRAPHICS_EXTENSIONS: &[&str] = &[
+    "pdf", "png", "jpg", "mps", "jpeg", "PDF", "PNG", "JPG", "JPEG", "eps",
+];
+
+/// List every \includegraphics target and whether it resolves to an existing file
+#[tauri::command]
+pub fn figures_check(project_dir: String) -> ApiResponse<Vec<FigureRef>> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let project_path = PathBuf::from(&project_dir);
+    let doc = match resolve_document(&project_path, &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let lines: Vec<(&SourceLocation, &str)> = doc.source_lines().collect();
+    let graphics_dirs = collect_graphics_dirs(&lines);
+    ApiResponse::success(collect_figures(&project_path, &lines, &graphics_dirs))
+}
+
+fn includegraphics_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\includegraphics\*?(?:\s*\[[^\]]*\])*\s*\{([^}]+)\}").unwrap())
+}
+
+// Every \includegraphics in `lines`, resolved against `graphics_dirs`
+fn collect_figures(project_dir: &Path, lines: &[(&SourceLocation, &str)], graphics_dirs: &[String]) -> Vec<FigureRef> {
+    let mut figures = Vec::new();
+    for (source, text) in lines {
+        for caps in includegraphics_regex().captures_iter(strip_comment(text)) {
+            let path = caps[1].trim().to_string();
+            let resolved = resolve_graphic(project_dir, graphics_dirs, &path);
+
+            figures.push(FigureRef {
+                file: source.file.clone(),
+                line: source.line,
+                command_line: caps[0].to_string(),
+                exists: resolved.is_some(),
+                resolved_path: resolved.unwrap_or_else(|| path.clone()),
+                path,
+            });
+        }
+    }
+
+    figures
+}
+
+/// Project files nothing refers to: images in the graphics directories (figures/ and
+/// \graphicspath entries) no \includegraphics resolves to, uncited bibliography entries, and
+/// .bib files the document never names
+#[tauri::command]
+pub fn project_unused(project_dir: String) -> ApiResponse<UnusedReport> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let project_path = PathBuf::from(&project_dir);
+    let doc = match resolve_document(&project_path, &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let lines: Vec<(&SourceLocation, &str)> = doc.source_lines().collect();
+    let graphics_dirs = collect_graphics_dirs(&lines);
+    let used: HashSet<String> = collect_figures(&project_path, &lines, &graphics_dirs)
+        .into_iter()
+        .filter(|figure| figure.exists)
+        .map(|figure| normalize_rel_path(&figure.resolved_path))
+        .collect();
+
+    // The project root is a graphics directory too, but holds far more than figures
+    let mut figures = BTreeSet::new();
+    for dir in graphics_dirs.iter().filter(|dir| !dir.is_empty()) {
+        for path in project_files(&project_path, dir, &config) {
+            let is_image = Path::new(&path)
+                .extension()
+                .is_some_and(|ext| GRAPHICS_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()));
+            if is_image && !used.contains(&path) {
+                figures.insert(path);
+            }
+        }
+    }
+
+    let bib_files = project_bib_files(&project_path, &doc);
+    let bib_entries = match uncited_entries(&project_path, &doc, &bib_files) {
+        Ok(entries) => entries,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let referenced: HashSet<String> = bib_files.iter().map(|file| normalize_rel_path(file)).collect();
+    let unreferenced_bib_files = project_files(&project_path, "", &config)
+        .into_iter()
+        .filter(|path| path.ends_with(".bib") && !referenced.contains(path))
+        .collect();
+
+    ApiResponse::success(UnusedReport {
+        figures: figures.into_iter().collect(),
+        bib_entries,
+        bib_files: unreferenced_bib_files,
+    })
+}
+
+// Files under `dir` (relative to the project), skipping the output directory, .easypaper and .git
+fn project_files(project_dir: &Path, dir: &str, config: &ProjectConfig) -> Vec<String> {
+    let out_dir = config.out_dir(&project_dir.to_string_lossy());
+    let root = std::path::absolute(project_dir).unwrap_or_else(|_| project_dir.to_path_buf());
+
+    let walker = WalkDir::new(root.join(dir)).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.path() != out_dir && name != ".easypaper" && name != ".git"
+    });
+    let mut files: Vec<String> = walker
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let rel_path = entry.path().strip_prefix(&root).ok()?;
+            Some(rel_path.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+// "./figures//a.png" -> "figures/a.png", so paths from the document compare with walked ones
+fn normalize_rel_path(path: &str) -> String {
+    path.replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn graphicspath_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\graphicspath\s*\{((?:\{[^}]*\}\s*)*)\}").unwrap())
+}
+
+fn braced_group_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{([^}]*)\}").unwrap())
+}
+
+// Directories searched for graphics: the project root, \graphicspath entries, then figures/
+fn collect_graphics_dirs(lines: &[(&SourceLocation, &str)]) -> Vec<String> {
+    let mut dirs = vec![String::new()];
+    for (_, text) in lines {
+        if let Some(caps) = graphicspath_regex().captures(strip_comment(text)) {
+            for entry in braced_group_regex().captures_iter(&caps[1]) {
+                let dir = entry[1].trim().trim_start_matches("./").to_string();
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+    if !dirs.iter().any(|d| d.trim_end_matches('/') == "figures") {
+        dirs.push("figures/".to_string());
+    }
+
+    dirs
+}
+
+// Resolves a graphics path the way graphicx does, returning the path relative to the project
+fn resolve_graphic(project_dir: &Path, dirs: &[String], path: &str) -> Option<String> {
+    let has_extension = Path::new(path).extension().is_some();
+
+    for dir in dirs {
+        let base = if dir.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", dir.trim_end_matches('/'), path)
+        };
+
+        if has_extension && project_dir.join(&base).is_file() {
+            return Some(base);
+        }
+        for ext in GRAPHICS_EXTENSIONS {
+            let candidate = format!("{}.{}", base, ext);
+            if project_dir.join(&candidate).is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+fn sectioning_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\\(chapter|section|subsection|subsubsection|paragraph)(\*?)\s*(?:\[[^\]]*\])?\s*\{").unwrap()
+    })
+}
+
+/// Build the document outline (chapters down to paragraphs) of the resolved document
+#[tauri::command]
+pub fn outline_get(project_dir: String) -> ApiResponse<Vec<OutlineItem>> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let doc = match resolve_document(Path::new(&project_dir), &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+    let lines: Vec<(&SourceLocation, &str)> = doc.source_lines().collect();
+
+    let mut outline = Vec::new();
+    for (idx, (source, text)) in lines.iter().enumerate() {
+        let text = strip_comment(text);
+        for caps in sectioning_regex().captures_iter(text) {
+            let level = SECTION_LEVELS
+                .iter()
+                .find(|(name, _)| *name == &caps[1])
+                .map(|(_, level)| *level)
+                .unwrap_or(1);
+
+            // Titles may run over several lines of the same file
+            let start = caps.get(0).unwrap().end();
+            let mut rest = text[start..].to_string();
+            for (next, next_text) in lines.iter().skip(idx + 1).take(5) {
+                if next.file != source.file || braced_end(&rest).is_some() {
+                    break;
+                }
+                rest.push(' ');
+                rest.push_str(strip_comment(next_text).trim());
+            }
+            let title = match braced_end(&rest) {
+                Some(end) => rest[..end].trim().to_string(),
+                None => rest.trim().to_string(),
+            };
+
+            insert_outline_item(
+                &mut outline,
+                OutlineItem {
+                    title,
+                    level,
+                    numbered: caps[2].is_empty(),
+                    file: source.file.clone(),
+                    line: source.line,
+                    children: vec![],
+                },
+            );
+        }
+    }
+
+    ApiResponse::success(outline)
+}
+
+// Returns the byte offset of the brace closing a group whose opening brace was already consumed
+fn braced_end(text: &str) -> Option<usize> {
+    let mut depth = 1;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Appends an item under the last sibling with a shallower level
+fn insert_outline_item(siblings: &mut Vec<OutlineItem>, item: OutlineItem) {
+    if let Some(last) = siblings.last_mut() {
+        if last.level < item.level {
+            insert_outline_item(&mut last.children, item);
+            return;
+        }
+    }
+    siblings.push(item);
+}
+
+/// Expand every \input/\include into a single source, optionally writing it to `output`
+#[tauri::command]
+pub fn document_flatten(project_dir: String, output: Option<String>) -> ApiResponse<String> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let doc = match resolve_document(Path::new(&project_dir), &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    if let Some(output) = output {
+        if let Err(e) = std::fs::write(&output, &doc.text) {
+            return ApiResponse::error(format!("Failed to write flattened document: {}", e));
+        }
+    }
+
+    ApiResponse::success(doc.text)
+}
+
+fn section_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\section\*?\s*[\[{]").unwrap())
+}
+
+fn figure_env_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\begin\{figure\*?\}").unwrap())
+}
+
+fn table_env_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\begin\{table\*?\}").unwrap())
+}
+
+fn cite_command_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\[a-zA-Z]*cite[a-zA-Z]*\*?(?:\s*\[[^\]]*\]){0,2}\s*\{").unwrap())
+}
+
+// `\\[2pt]` is a line break, not display math
+fn equation_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\begin\{equation\*?\}|(?:^|[^\\])\\\[").unwrap())
+}
+
+/// Counts for a project summary, taken from the resolved document in one pass
+#[tauri::command]
+pub fn project_stats(project_dir: String) -> ApiResponse<ProjectStats> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let doc = match resolve_document(Path::new(&project_dir), &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let mut stats = ProjectStats {
+        files: doc.files.len(),
+        cited_keys: cited_keys(&doc).iter().filter(|key| *key != "*").count(),
+        ..Default::default()
+    };
+    let mut scanner = ProseScanner::default();
+    let mut in_body = false;
+
+    for (_, text) in doc.source_lines() {
+        let code = strip_comment(text);
+        stats.sections += section_regex().find_iter(code).count();
+        stats.figures += figure_env_regex().find_iter(code).count();
+        stats.tables += table_env_regex().find_iter(code).count();
+        stats.citations += cite_command_regex().find_iter(code).count();
+        stats.equations += equation_regex().find_iter(code).count();
+
+        // The scanner still runs over the preamble to keep its environment state right
+        let words = scanner.words(text).len();
+        if in_body {
+            stats.words += words;
+        }
+        in_body = in_body || code.contains("\\begin{document}");
+    }
+
+    ApiResponse::success(stats)
+}
+
+/// Title, authors and abstract of the document, as plain text
+#[tauri::command]
+pub fn project_metadata(project_dir: String) -> ApiResponse<DocMeta> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let doc = match resolve_document(Path::new(&project_dir), &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+    let code: String = doc
+        .source_lines()
+        .map(|(_, text)| strip_comment(text))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let title = command_arguments(&code, "title").into_iter().next().map(|t| clean_tex(&t));
+
+    let mut authors = Vec::new();
+    for arg in command_arguments(&code, "author") {
+        // IEEEtran puts every author of the paper in one \author with a block per name
+        let blocks = command_arguments(&arg, "IEEEauthorblockN");
+        let names: Vec<String> = if blocks.is_empty() {
+            // Names come first; affiliation lines follow after \\
+            arg.split("\\and")
+                .map(|part| part.split("\\\\").next().unwrap_or(part).to_string())
+                .collect()
+        } else {
+            blocks
+        };
+        authors.extend(
+            names
+                .iter()
+                .map(|name| clean_tex(name))
+                .filter(|name| !name.is_empty()),
+        );
+    }
+
+    let r#abstract = match (code.find("\\begin{abstract}"), code.find("\\end{abstract}")) {
+        (Some(start), Some(end)) if start < end => Some(&code[start + "\\begin{abstract}".len()..end]),
+        _ => None,
+    }
+    .map(|text| text.to_string())
+    .or_else(|| command_arguments(&code, "abstract").into_iter().next())
+    .map(|text| {
+        // Keep paragraph breaks, flatten everything else
+        text.split("\n\n")
+            .map(clean_tex)
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    })
+    .filter(|text| !text.is_empty());
+
+    ApiResponse::success(DocMeta {
+        title,
+        authors,
+        r#abstract,
+    })
+}
+
+// Span of every `\name[...]{...}` in `text` together with the span of its braced argument,
+// allowing nested braces and newlines
+fn command_spans(text: &str, name: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    let re = command_regex(name);
+    re.find_iter(text)
+        .filter_map(|m| braced_end(&text[m.end()..]).map(|end| (m.start()..m.end() + end + 1, m.end()..m.end() + end)))
+        .collect()
+}
+
+fn command_regex(name: &str) -> Regex {
+    static CACHE: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+    let mut cache = CACHE.get_or_init(Default::default).lock().unwrap();
+    cache
+        .entry(name.to_string())
+        .or_insert_with(|| Regex::new(&format!(r"\\{}\*?\s*(?:\[[^\]]*\])?\s*\{{", regex::escape(name))).unwrap())
+        .clone()
+}
+
+// Braced arguments of every `\name[...]{...}` in `text`
+fn command_arguments(text: &str, name: &str) -> Vec<String> {
+    command_spans(text, name).into_iter().map(|(_, arg)| text[arg].to_string()).collect()
+}
+
+// Plain text from a LaTeX fragment: notes dropped, commands unwrapped, whitespace collapsed
+fn clean_tex(text: &str) -> String {
+    let mut text = text.to_string();
+    for note in ["thanks", "footnote", "IEEEauthorrefmark", "orcid", "inst"] {
+        let mut kept = String::with_capacity(text.len());
+        let mut last = 0;
+        for (span, _) in command_spans(&text, note) {
+            // A nested note of the same kind sits inside a span already dropped
+            if span.start < last {
+                continue;
+            }
+            kept.push_str(&text[last..span.start]);
+            last = span.end;
+        }
+        kept.push_str(&text[last..]);
+        text = kept;
+    }
+
+    let text = text.replace("\\\\", " ").replace('~', " ");
+    let text = latex_command_regex().replace_all(&text, "");
+    let text: String = text.chars().filter(|c| !matches!(c, '{' | '}' | '$')).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn latex_command_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\[a-zA-Z@]+\*?").unwrap())
+}
+
+/// Commands, math operators and environments the project defines, for autocomplete and
+/// jump to definition
+#[tauri::command]
+pub fn macros_list(project_dir: String) -> ApiResponse<Vec<MacroDef>> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let doc = match resolve_document(Path::new(&project_dir), &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let mut macros = Vec::new();
+    for (source, text) in doc.source_lines() {
+        let code = strip_comment(text);
+        let mut push = |definition: &str, name: &str, arity: Option<&str>, has_default: bool| {
+            macros.push(MacroDef {
+                name: name.to_string(),
+                definition: definition.to_string(),
+                arity: arity.and_then(|n| n.parse().ok()).unwrap_or(0),
+                has_default,
+                file: source.file.clone(),
+                line: source.line,
+            });
+        };
+
+        for caps in newcommand_regex().captures_iter(code) {
+            let name = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+            push(&caps[1], name, caps.get(4).map(|m| m.as_str()), caps.get(5).is_some());
+        }
+        for caps in math_operator_regex().captures_iter(code) {
+            let name = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+            push(&caps[1], name, None, false);
+        }
+        for caps in newenvironment_regex().captures_iter(code) {
+            push(&caps[1], &caps[2], caps.get(3).map(|m| m.as_str()), caps.get(4).is_some());
+        }
+    }
+
+    ApiResponse::success(macros)
+}
+
+// Both `\newcommand{\foo}[2][x]{...}` and `\newcommand\foo{...}`
+fn newcommand_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"\\(newcommand|renewcommand|providecommand)\*?\s*(?:\{\s*(\\[a-zA-Z@]+)\s*\}|(\\[a-zA-Z@]+))\s*(?:\[\s*(\d)\s*\])?\s*(\[)?",
+        )
+        .unwrap()
+    })
+}
+
+fn math_operator_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\\(DeclareMathOperator)\*?\s*(?:\{\s*(\\[a-zA-Z@]+)\s*\}|(\\[a-zA-Z@]+))").unwrap())
+}
+
+fn newenvironment_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\\(newenvironment|renewenvironment)\*?\s*\{\s*([a-zA-Z@*]+)\s*\}\s*(?:\[\s*(\d)\s*\])?\s*(\[)?")
+            .unwrap()
+    })
+}
@@ -0,0 +1,315 @@
+use crate::document::{resolve_document, strip_comment};
+use crate::project::ProjectConfig;
+use crate::svc_file::ApiResponse;
+use crate::svc_project::ProjectState;
+use crate::svc_synctex::find_synctex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineInfo {
+    pub name: String,
+    pub found: bool,
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+const ENGINES: &[&str] = &["tectonic", "latexmk", "xelatex", "lualatex", "pdflatex"];
+
+// Engines that run through latexmk, with the flag that selects them
+const LATEXMK_ENGINES: &[(&str, &str)] = &[("xelatex", "-xelatex"), ("lualatex", "-lualatex"), ("pdflatex", "-pdf")];
+
+// How long a detection result is reused before probing again
+const DETECT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+type DetectCache = Mutex<Option<(Instant, Vec<EngineInfo>)>>;
+
+fn detect_cache() -> &'static DetectCache {
+    static CACHE: OnceLock<DetectCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// Looks up an executable on PATH
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| executable_in(&dir, name))
+}
+
+/// The executable `name` in `dir`, if there is one
+pub fn executable_in(dir: &Path, name: &str) -> Option<PathBuf> {
+    let candidate = dir.join(name);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    if cfg!(windows) {
+        let exe = dir.join(format!("{}.exe", name));
+        if exe.is_file() {
+            return Some(exe);
+        }
+    }
+    None
+}
+
+/// The executable `name` from a TeX distribution's `bin_dir`, falling back to PATH
+pub fn find_tool(bin_dir: Option<&Path>, name: &str) -> Option<PathBuf> {
+    bin_dir
+        .and_then(|dir| executable_in(dir, name))
+        .or_else(|| find_in_path(name))
+}
+
+/// PATH with `dir` searched first
+pub fn path_with_dir(dir: &Path) -> Option<OsString> {
+    let existing = std::env::var_os("PATH").unwrap_or_default();
+    let dirs = std::iter::once(dir.to_path_buf()).chain(std::env::split_paths(&existing));
+    std::env::join_paths(dirs).ok()
+}
+
+/// First line of `<program> --version`
+pub fn program_version(program: &Path) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())
+        .map(|l| l.to_string())
+}
+
+fn probe_engine(name: &str, bin_dir: Option<&Path>) -> EngineInfo {
+    match find_tool(bin_dir, name) {
+        Some(path) => EngineInfo {
+            name: name.to_string(),
+            found: true,
+            version: program_version(&path),
+            path: Some(path.to_string_lossy().to_string()),
+        },
+        None => EngineInfo {
+            name: name.to_string(),
+            found: false,
+            path: None,
+            version: None,
+        },
+    }
+}
+
+// Engine probes, reusing the cached result unless it is stale or `refresh` is set
+fn detected_engines(refresh: bool) -> Result<Vec<EngineInfo>, String> {
+    let mut cache = detect_cache()
+        .lock()
+        .map_err(|e| format!("Failed to access engine cache: {}", e))?;
+
+    if !refresh {
+        if let Some((probed_at, engines)) = cache.as_ref() {
+            if probed_at.elapsed() < DETECT_CACHE_TTL {
+                return Ok(engines.clone());
+            }
+        }
+    }
+
+    let engines: Vec<EngineInfo> = ENGINES.iter().map(|name| probe_engine(name, None)).collect();
+    *cache = Some((Instant::now(), engines.clone()));
+    Ok(engines)
+}
+
+/// Probes which LaTeX engines are installed. Results are cached briefly;
+/// pass `refresh` to probe again immediately.
+#[tauri::command]
+pub fn engine_detect(refresh: Option<bool>) -> ApiResponse<Vec<EngineInfo>> {
+    match detected_engines(refresh.unwrap_or(false)) {
+        Ok(engines) => ApiResponse::success(engines),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvReport {
+    pub os: String,
+    pub arch: String,
+    pub app_version: String,
+    pub engines: Vec<EngineInfo>,
+    pub synctex: EngineInfo,
+    /// First line of `tex --version`, e.g. "TeX 3.141592653 (TeX Live 2024)"
+    pub tex_distribution: Option<String>,
+}
+
+/// Everything about the local TeX setup worth pasting into a bug report. With a project, its
+/// `tex_dist_path` is reported on rather than PATH.
+#[tauri::command]
+pub fn env_report(project_dir: Option<String>) -> ApiResponse<EnvReport> {
+    // A project that fails to load still gets the report for PATH
+    let bin_dir = project_dir
+        .and_then(|dir| ProjectConfig::load(&dir).ok())
+        .and_then(|config| config.tex_bin_dir().ok().flatten());
+
+    let engines = match bin_dir.as_deref() {
+        Some(dir) => ENGINES.iter().map(|name| probe_engine(name, Some(dir))).collect(),
+        None => match detected_engines(true) {
+            Ok(engines) => engines,
+            Err(e) => return ApiResponse::error(e),
+        },
+    };
+
+    let synctex = match find_synctex(bin_dir.as_deref()) {
+        Some(path) => EngineInfo {
+            name: "synctex".to_string(),
+            found: true,
+            version: program_version(&path),
+            path: Some(path.to_string_lossy().to_string()),
+        },
+        None => probe_engine("synctex", bin_dir.as_deref()),
+    };
+
+    ApiResponse::success(EnvReport {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        engines,
+        synctex,
+        tex_distribution: find_tool(bin_dir.as_deref(), "tex").and_then(|tex| program_version(&tex)),
+    })
+}
+
+/// Switch the project's build engine, refusing engines that aren't installed.
+/// xelatex, lualatex and pdflatex build through latexmk with the matching flag.
+#[tauri::command]
+pub fn engine_set(project_dir: String, engine_type: String, state: State<'_, ProjectState>) -> ApiResponse<ProjectConfig> {
+    let mut config = match state.config_for(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let latexmk_flag = LATEXMK_ENGINES
+        .iter()
+        .find(|(engine, _)| *engine == engine_type)
+        .map(|(_, flag)| *flag);
+
+    config.engine.engine_type = match latexmk_flag {
+        Some(_) => "latexmk".to_string(),
+        None => engine_type.clone(),
+    };
+    // Only one engine flag may be in effect, and only for latexmk
+    config
+        .engine
+        .args
+        .retain(|arg| !LATEXMK_ENGINES.iter().any(|(_, flag)| flag == arg));
+    if let Some(flag) = latexmk_flag {
+        config.engine.args.push(flag.to_string());
+    }
+    if let Err(e) = config.validate() {
+        return ApiResponse::error(e);
+    }
+
+    let bin_dir = config.tex_bin_dir().ok().flatten();
+    for program in [config.engine.engine_type.as_str(), engine_type.as_str()] {
+        if find_tool(bin_dir.as_deref(), program).is_none() {
+            return ApiResponse::error(format!("{} is not installed", program));
+        }
+    }
+
+    match config.save(&project_dir) {
+        Ok(_) => {
+            // Builds read the open project's cached config
+            state.set_config(&project_dir, &config);
+            ApiResponse::success(config)
+        }
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageStatus {
+    pub name: String,
+    /// First place the package is loaded
+    pub file: String,
+    pub line: u32,
+    pub found: bool,
+    /// Location of the .sty file reported by kpsewhich
+    pub path: Option<String>,
+    /// Tectonic fetches packages on demand, so nothing is checked up front
+    pub deferred: bool,
+}
+
+fn usepackage_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\\(?:usepackage|RequirePackage)\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap()
+    })
+}
+
+/// Checks that every \usepackage / \RequirePackage in the document is installed
+#[tauri::command]
+pub fn packages_check(project_dir: String) -> ApiResponse<Vec<PackageStatus>> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let doc = match resolve_document(Path::new(&project_dir), &config.main) {
+        Ok(doc) => doc,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let mut packages: Vec<PackageStatus> = Vec::new();
+    for (source, text) in doc.source_lines() {
+        for caps in usepackage_regex().captures_iter(strip_comment(text)) {
+            for name in caps[1].split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                if packages.iter().any(|p| p.name == name) {
+                    continue;
+                }
+                packages.push(PackageStatus {
+                    name: name.to_string(),
+                    file: source.file.clone(),
+                    line: source.line,
+                    found: false,
+                    path: None,
+                    deferred: false,
+                });
+            }
+        }
+    }
+
+    if config.engine.engine_type == "tectonic" {
+        for package in &mut packages {
+            package.deferred = true;
+        }
+        return ApiResponse::success(packages);
+    }
+
+    if packages.is_empty() {
+        return ApiResponse::success(packages);
+    }
+
+    let bin_dir = config.tex_bin_dir().ok().flatten();
+    let Some(kpsewhich) = find_tool(bin_dir.as_deref(), "kpsewhich") else {
+        return ApiResponse::error("kpsewhich not found; cannot check installed packages".to_string());
+    };
+
+    // One kpsewhich call for all packages; it prints a path for each file it finds
+    let output = match Command::new(kpsewhich)
+        .current_dir(&project_dir)
+        .args(packages.iter().map(|p| format!("{}.sty", p.name)))
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => return ApiResponse::error(format!("Failed to run kpsewhich: {}", e)),
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let Some(stem) = Path::new(line).file_stem().map(|s| s.to_string_lossy()) else {
+            continue;
+        };
+        if let Some(package) = packages.iter_mut().find(|p| p.name == stem && !p.found) {
+            package.found = true;
+            package.path = Some(line.to_string());
+        }
+    }
+
+    ApiResponse::success(packages)
+}
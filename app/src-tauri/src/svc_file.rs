@@ -1,6 +1,14 @@
+use crate::project::ProjectConfig;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_8};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{Emitter, Window};
 use walkdir::WalkDir;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,11 +19,53 @@ pub struct FileInfo {
     pub size: u64,
 }
 
+/// An entry a listing could not read, e.g. a folder without permission or a broken symlink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedEntry {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileListing {
+    pub files: Vec<FileInfo>,
+    pub skipped: Vec<SkippedEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRange {
+    pub content: String,
+    /// Byte offset and length actually returned, after trimming to character boundaries
+    pub offset: u64,
+    pub length: u64,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodedFile {
+    pub content: String,
+    /// WHATWG name of the encoding the file was decoded from, e.g. "UTF-8", "windows-1252", "GBK"
+    pub encoding: String,
+    /// Whether some bytes were invalid in that encoding and got replaced
+    pub had_errors: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMeta {
+    pub encoding: String,
+    /// "lf", "crlf", "mixed", or "none" for a file without line breaks
+    pub line_ending: String,
+    pub has_bom: bool,
+    pub trailing_newline: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub ok: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Machine-readable error code for errors the UI handles specially
+    pub code: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -24,6 +74,7 @@ impl<T> ApiResponse<T> {
             ok: true,
             data: Some(data),
             error: None,
+            code: None,
         }
     }
 
@@ -32,6 +83,14 @@ impl<T> ApiResponse<T> {
             ok: false,
             data: None,
             error: Some(msg),
+            code: None,
+        }
+    }
+
+    pub fn error_with_code(code: &str, msg: String) -> Self {
+        ApiResponse {
+            code: Some(code.to_string()),
+            ..Self::error(msg)
         }
     }
 }
@@ -48,20 +107,178 @@ pub fn file_read(path: String) -> ApiResponse<String> {
         return ApiResponse::error(format!("Path is not a file: {}", path));
     }
 
-    match fs::read_to_string(&path) {
-        Ok(content) => ApiResponse::success(content),
-        Err(e) => ApiResponse::error(format!("Failed to read file '{}': {} (kind: {:?})", path, e, e.kind())),
+    match read_decoded(&path, None) {
+        Ok(file) => ApiResponse::success(file.content),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+/// Like `file_read`, but reports the encoding; `encoding` forces a specific one ("reopen with encoding")
+#[tauri::command]
+pub fn file_read_encoded(path: String, encoding: Option<String>) -> ApiResponse<EncodedFile> {
+    match read_decoded(&path, encoding.as_deref()) {
+        Ok(file) => ApiResponse::success(file),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+fn encoding_for_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| format!("Unknown encoding: {}", label))
+}
+
+// Reads a text file in any encoding; without a forced encoding, a BOM or valid UTF-8 wins
+// and anything else is guessed (Latin-1, GBK, Shift_JIS, ...)
+fn read_decoded(path: &str, forced: Option<&str>) -> Result<EncodedFile, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read file '{}': {} (kind: {:?})", path, e, e.kind()))?;
+    decode_bytes(&bytes, forced)
+}
+
+fn decode_bytes(bytes: &[u8], forced: Option<&str>) -> Result<EncodedFile, String> {
+    let encoding = match forced {
+        Some(label) => encoding_for_label(label)?,
+        None => match Encoding::for_bom(bytes) {
+            Some((bom_encoding, _)) => bom_encoding,
+            None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+            None => {
+                // Valid UTF-8 was ruled out above
+                let mut detector = EncodingDetector::new(Iso2022JpDetection::Deny);
+                detector.feed(bytes, true);
+                detector.guess(None, Utf8Detection::Deny)
+            }
+        },
+    };
+
+    let (content, used, had_errors) = encoding.decode(bytes);
+    Ok(EncodedFile {
+        content: content.into_owned(),
+        encoding: used.name().to_string(),
+        had_errors,
+    })
+}
+
+// "lf", "crlf", "mixed" or "none", judged on the decoded text
+fn line_ending_style(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    match (lf, crlf) {
+        (0, 0) => "none",
+        (_, 0) => "lf",
+        (0, _) => "crlf",
+        _ => "mixed",
+    }
+}
+
+/// Encoding, line endings, BOM and trailing newline of a text file, so saving can keep them
+#[tauri::command]
+pub fn file_inspect(path: String) -> ApiResponse<FileMeta> {
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => return ApiResponse::error(format!("Failed to read file '{}': {}", path, e)),
+    };
+    let file = match decode_bytes(&bytes, None) {
+        Ok(file) => file,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    ApiResponse::success(FileMeta {
+        line_ending: line_ending_style(&file.content).to_string(),
+        has_bom: Encoding::for_bom(&bytes).is_some(),
+        trailing_newline: file.content.ends_with('\n'),
+        encoding: file.encoding,
+    })
+}
+
+#[tauri::command]
+pub fn file_read_range(path: String, offset: u64, length: u64) -> ApiResponse<FileRange> {
+    let mut file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) => return ApiResponse::error(format!("Failed to open file '{}': {}", path, e)),
+    };
+
+    let total_size = match file.metadata() {
+        Ok(meta) => meta.len(),
+        Err(e) => return ApiResponse::error(format!("Failed to read file metadata: {}", e)),
+    };
+
+    let offset = offset.min(total_size);
+    let length = length.min(total_size - offset);
+
+    if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+        return ApiResponse::error(format!("Failed to seek in file: {}", e));
+    }
+
+    let mut buffer = Vec::with_capacity(length as usize);
+    if let Err(e) = file.take(length).read_to_end(&mut buffer) {
+        return ApiResponse::error(format!("Failed to read file: {}", e));
+    }
+
+    // Drop continuation bytes of a character that started before the window
+    let start = buffer
+        .iter()
+        .take(3)
+        .take_while(|b| (**b & 0b1100_0000) == 0b1000_0000)
+        .count();
+
+    // Drop a character cut off by the end of the window
+    let mut end = buffer.len();
+    if let Err(e) = std::str::from_utf8(&buffer[start..]) {
+        if e.error_len().is_none() {
+            end = start + e.valid_up_to();
+        }
     }
+
+    let content = String::from_utf8_lossy(&buffer[start..end]).to_string();
+
+    ApiResponse::success(FileRange {
+        content,
+        offset: offset + start as u64,
+        length: (end - start) as u64,
+        total_size,
+    })
 }
 
+/// Writes `content` as UTF-8 unless another `encoding` is given (e.g. to keep a file in Latin-1).
+/// With `backup`, the previous content is first kept under `.easypaper/backups/`.
 #[tauri::command]
-pub fn file_write(path: String, content: String, create: Option<bool>) -> ApiResponse<()> {
+pub fn file_write(
+    path: String,
+    content: String,
+    create: Option<bool>,
+    encoding: Option<String>,
+    preserve_line_endings: Option<bool>,
+    backup: Option<bool>,
+) -> ApiResponse<()> {
     let should_create = create.unwrap_or(false);
 
     if !should_create && !Path::new(&path).exists() {
         return ApiResponse::error("File does not exist".to_string());
     }
 
+    // Rewrite the editor's line breaks to the file's existing LF or CRLF convention
+    let content = if preserve_line_endings.unwrap_or(false) {
+        match read_decoded(&path, encoding.as_deref()).map(|f| line_ending_style(&f.content)) {
+            Ok("crlf") => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+            Ok("lf") => content.replace("\r\n", "\n"),
+            // New, mixed or unbroken files are written as given
+            _ => content,
+        }
+    } else {
+        content
+    };
+
+    let bytes = match encoding.as_deref().map(encoding_for_label) {
+        None => content.into_bytes(),
+        Some(Ok(target)) => {
+            let (bytes, _, had_errors) = target.encode(&content);
+            if had_errors {
+                return ApiResponse::error(format!("Content cannot be represented in {}", target.name()));
+            }
+            bytes.into_owned()
+        }
+        Some(Err(e)) => return ApiResponse::error(e),
+    };
+
     // Create parent directories if needed
     if let Some(parent) = Path::new(&path).parent() {
         if !parent.exists() {
@@ -71,28 +288,261 @@ pub fn file_write(path: String, content: String, create: Option<bool>) -> ApiRes
         }
     }
 
-    match fs::write(&path, content) {
+    if backup.unwrap_or(false) && Path::new(&path).is_file() {
+        if let Err(e) = backup_file(Path::new(&path)) {
+            return ApiResponse::error(format!("Failed to back up file: {}", e));
+        }
+    }
+
+    match write_atomic(Path::new(&path), bytes) {
         Ok(_) => ApiResponse::success(()),
-        Err(e) => ApiResponse::error(format!("Failed to write file: {}", e)),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBackup {
+    /// Milliseconds since the Unix epoch when the backup was taken
+    pub id: String,
+    pub size: u64,
+}
+
+// Project-relative directory of the backups, as an ignore rule so listings leave it out
+const BACKUPS_DIR: &str = ".easypaper/backups/";
+
+// Where `path`'s backups go: the project root (the nearest ancestor with a .easypaper directory,
+// else the file's own directory), the backup directory, and the prefix of the backup names
+fn backup_location(path: &Path) -> Result<(PathBuf, PathBuf, String), String> {
+    let path = std::path::absolute(path).map_err(|e| format!("Invalid file path: {}", e))?;
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(format!("Invalid file path: {}", path.display()));
+    };
+    let root = parent
+        .ancestors()
+        .find(|dir| dir.join(".easypaper").is_dir())
+        .unwrap_or(parent);
+    let rel_dir = parent.strip_prefix(root).unwrap_or(Path::new(""));
+
+    Ok((
+        root.to_path_buf(),
+        root.join(BACKUPS_DIR).join(rel_dir),
+        format!("{}.", name.to_string_lossy()),
+    ))
+}
+
+// Backups of `path`, newest first, with the file each one is stored in
+fn list_backups(path: &Path) -> Result<Vec<(FileBackup, PathBuf)>, String> {
+    let (_, dir, prefix) = backup_location(path)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read backups: {}", e)),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let id = name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(".bak"));
+        let Some(id) = id.filter(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())) else {
+            continue;
+        };
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        backups.push((FileBackup { id: id.to_string(), size }, entry.path()));
     }
+
+    backups.sort_by_key(|(backup, _)| std::cmp::Reverse(backup.id.parse::<u128>().unwrap_or(0)));
+    Ok(backups)
 }
 
+// Copies `path` to `<name>.<millis>.bak` and prunes backups beyond the project's `max_backups`
+fn backup_file(path: &Path) -> Result<Option<FileBackup>, String> {
+    let (root, dir, prefix) = backup_location(path)?;
+    let max_backups = ProjectConfig::load(&root.to_string_lossy())
+        .map(|config| config.max_backups)
+        .unwrap_or_else(|_| ProjectConfig::default().max_backups);
+    if max_backups == 0 {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    // Ids only grow, so saves within the same millisecond still get separate, ordered backups
+    let newest = list_backups(path)?
+        .first()
+        .and_then(|(backup, _)| backup.id.parse::<u128>().ok());
+    let millis = newest.map_or(now, |newest| now.max(newest + 1));
+    let target = dir.join(format!("{}{}.bak", prefix, millis));
+    let size = fs::copy(path, &target).map_err(|e| format!("Failed to copy file: {}", e))?;
+
+    for (_, old) in list_backups(path)?.into_iter().skip(max_backups) {
+        let _ = fs::remove_file(old);
+    }
+
+    Ok(Some(FileBackup {
+        id: millis.to_string(),
+        size,
+    }))
+}
+
+/// Backups `file_write` kept of a file, newest first
+#[tauri::command]
+pub fn file_backups_list(path: String) -> ApiResponse<Vec<FileBackup>> {
+    match list_backups(Path::new(&path)) {
+        Ok(backups) => ApiResponse::success(backups.into_iter().map(|(backup, _)| backup).collect()),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+/// Replaces the file with backup `backup_id`. The current content is backed up first, and that
+/// backup is returned so the revert can be undone.
+#[tauri::command]
+pub fn file_backup_restore(path: String, backup_id: String) -> ApiResponse<Option<FileBackup>> {
+    let backups = match list_backups(Path::new(&path)) {
+        Ok(backups) => backups,
+        Err(e) => return ApiResponse::error(e),
+    };
+    let Some((_, source)) = backups.into_iter().find(|(backup, _)| backup.id == backup_id) else {
+        return ApiResponse::error(format!("Backup not found: {}", backup_id));
+    };
+
+    // Read before backing up the current content, which may prune this very backup
+    let content = match fs::read(&source) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read backup: {}", e)),
+    };
+    let current = if Path::new(&path).is_file() {
+        match backup_file(Path::new(&path)) {
+            Ok(backup) => backup,
+            Err(e) => return ApiResponse::error(format!("Failed to back up file: {}", e)),
+        }
+    } else {
+        None
+    };
+
+    match write_atomic(Path::new(&path), content) {
+        Ok(_) => ApiResponse::success(current),
+        Err(e) => ApiResponse::error(format!("Failed to restore backup: {}", e)),
+    }
+}
+
+/// Largest file whose bytes are sent over IPC as base64
+pub const MAX_BINARY_READ_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Base64 of the file's bytes, or None when it exceeds `MAX_BINARY_READ_BYTES`
+pub fn read_base64(path: &Path) -> Result<Option<String>, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+    if size > MAX_BINARY_READ_BYTES {
+        return Ok(None);
+    }
+
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+    Ok(Some(BASE64.encode(bytes)))
+}
+
+/// Writes through a temporary sibling file and a rename so readers never see a partial file.
+/// A symlink is written through to its target, which keeps its permissions.
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> Result<(), String> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let path = target.as_path();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    fs::write(&tmp_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    if let Ok(meta) = fs::metadata(path) {
+        let _ = fs::set_permissions(&tmp_path, meta.permissions());
+    }
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to replace file: {}", e)
+    })
+}
+
+// `*` and `?` wildcards, as used by the gitignore template
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(p: &[char], n: &[char]) -> bool {
+        match p.split_first() {
+            None => n.is_empty(),
+            Some(('*', rest)) => (0..=n.len()).any(|i| matches(rest, &n[i..])),
+            Some(('?', rest)) => !n.is_empty() && matches(rest, &n[1..]),
+            Some((c, rest)) => n.first() == Some(c) && matches(rest, &n[1..]),
+        }
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    matches(&p, &n)
+}
+
+// Whether the gitignore-style `rules` exclude a project-relative path
+pub(crate) fn is_ignored(rules: &[&str], rel_path: &str, is_dir: bool) -> bool {
+    let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    rules.iter().any(|rule| match rule.strip_suffix('/') {
+        Some(dir_rule) if is_dir => {
+            if dir_rule.contains('/') {
+                rel_path == dir_rule
+            } else {
+                glob_match(dir_rule, name)
+            }
+        }
+        Some(_) => false,
+        None => glob_match(rule, name),
+    })
+}
+
+fn relative_path(dir: &str, path: &Path) -> String {
+    path.strip_prefix(dir).unwrap_or(path).to_string_lossy().to_string()
+}
+
+fn skipped_entry(dir: &str, err: &walkdir::Error) -> SkippedEntry {
+    SkippedEntry {
+        path: err.path().map(|p| relative_path(dir, p)).unwrap_or_default(),
+        reason: err
+            .io_error()
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| err.to_string()),
+    }
+}
+
+// The caller's ignore rules plus the backups directory, which is never listed
+fn listing_rules(ignore: &[String]) -> Vec<&str> {
+    let mut rules: Vec<&str> = ignore.iter().map(String::as_str).collect();
+    rules.push(BACKUPS_DIR);
+    rules
+}
+
+/// Lists `dir`; `ignore` takes gitignore-style rules (`data/`, `*.log`) that are skipped entirely.
+/// Entries that can't be read are reported in `skipped` instead of failing the listing.
 #[tauri::command]
-pub fn file_list(dir: String, recursive: Option<bool>) -> ApiResponse<Vec<FileInfo>> {
+pub fn file_list(dir: String, recursive: Option<bool>, ignore: Option<Vec<String>>) -> ApiResponse<FileListing> {
     let is_recursive = recursive.unwrap_or(false);
+    let ignore = ignore.unwrap_or_default();
+    let rules: Vec<&str> = listing_rules(&ignore);
     let mut files = Vec::new();
+    let mut skipped = Vec::new();
 
     if is_recursive {
-        for entry in WalkDir::new(&dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        let walker = WalkDir::new(&dir).into_iter().filter_entry(|e| {
+            e.depth() == 0 || !is_ignored(&rules, &relative_path(&dir, e.path()), e.file_type().is_dir())
+        });
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if e.depth() == 0 => {
+                    return ApiResponse::error(format!("Failed to read directory: {}", e));
+                }
+                Err(e) => {
+                    skipped.push(skipped_entry(&dir, &e));
+                    continue;
+                }
+            };
             let path = entry.path();
-            let relative_path = path
-                .strip_prefix(&dir)
-                .unwrap_or(path)
-                .to_string_lossy()
-                .to_string();
+            let relative_path = relative_path(&dir, path);
 
             if relative_path.is_empty() {
                 continue;
@@ -108,9 +558,22 @@ pub fn file_list(dir: String, recursive: Option<bool>) -> ApiResponse<Vec<FileIn
     } else {
         match fs::read_dir(&dir) {
             Ok(entries) => {
-                for entry in entries.filter_map(|e| e.ok()) {
+                for entry in entries {
+                    let entry = match entry {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            skipped.push(SkippedEntry {
+                                path: String::new(),
+                                reason: e.to_string(),
+                            });
+                            continue;
+                        }
+                    };
                     let path = entry.path();
                     let name = entry.file_name().to_string_lossy().to_string();
+                    if is_ignored(&rules, &name, path.is_dir()) {
+                        continue;
+                    }
 
                     files.push(FileInfo {
                         name: name.clone(),
@@ -124,7 +587,78 @@ pub fn file_list(dir: String, recursive: Option<bool>) -> ApiResponse<Vec<FileIn
         }
     }
 
-    ApiResponse::success(files)
+    ApiResponse::success(FileListing { files, skipped })
+}
+
+pub const LIST_BATCH_EVENT: &str = "fs://list-batch";
+pub const LIST_DONE_EVENT: &str = "fs://list-done";
+
+// Entries per fs://list-batch event
+const LIST_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListBatchEvent {
+    pub id: u64,
+    pub entries: Vec<FileInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDoneEvent {
+    pub id: u64,
+    pub total: usize,
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// Recursive listing for very large trees. Returns a listing id at once, then emits the
+/// entries in `fs://list-batch` events and finishes with `fs://list-done`.
+#[tauri::command]
+pub fn file_list_stream(dir: String, window: Window, ignore: Option<Vec<String>>) -> ApiResponse<u64> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+    if !Path::new(&dir).is_dir() {
+        return ApiResponse::error(format!("Not a directory: {}", dir));
+    }
+
+    std::thread::spawn(move || {
+        let ignore = ignore.unwrap_or_default();
+        let rules: Vec<&str> = listing_rules(&ignore);
+        let mut batch = Vec::with_capacity(LIST_BATCH_SIZE);
+        let mut total = 0;
+        let mut skipped = Vec::new();
+
+        let walker = WalkDir::new(&dir).min_depth(1).into_iter().filter_entry(|e| {
+            !is_ignored(&rules, &relative_path(&dir, e.path()), e.file_type().is_dir())
+        });
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    skipped.push(skipped_entry(&dir, &e));
+                    continue;
+                }
+            };
+            batch.push(FileInfo {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: relative_path(&dir, entry.path()),
+                is_dir: entry.file_type().is_dir(),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+            total += 1;
+
+            if batch.len() == LIST_BATCH_SIZE {
+                let entries = std::mem::replace(&mut batch, Vec::with_capacity(LIST_BATCH_SIZE));
+                let _ = window.emit(LIST_BATCH_EVENT, ListBatchEvent { id, entries });
+            }
+        }
+
+        if !batch.is_empty() {
+            let _ = window.emit(LIST_BATCH_EVENT, ListBatchEvent { id, entries: batch });
+        }
+        let _ = window.emit(LIST_DONE_EVENT, ListDoneEvent { id, total, skipped });
+    });
+
+    ApiResponse::success(id)
 }
 
 #[tauri::command]
@@ -144,7 +678,23 @@ pub fn file_delete(path: String) -> ApiResponse<()> {
 }
 
 #[tauri::command]
-pub fn file_rename(old_path: String, new_path: String) -> ApiResponse<()> {
+pub fn file_rename(old_path: String, new_path: String, overwrite: Option<bool>) -> ApiResponse<()> {
+    let should_overwrite = overwrite.unwrap_or(false);
+
+    // fs::rename silently replaces an existing file on Unix
+    if !should_overwrite && Path::new(&new_path).exists() {
+        return ApiResponse::error("Destination already exists".to_string());
+    }
+
+    // Create parent directories if needed
+    if let Some(parent) = Path::new(&new_path).parent() {
+        if !parent.exists() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return ApiResponse::error(format!("Failed to create parent directories: {}", e));
+            }
+        }
+    }
+
     match fs::rename(&old_path, &new_path) {
         Ok(_) => ApiResponse::success(()),
         Err(e) => ApiResponse::error(format!("Failed to rename: {}", e)),
@@ -156,10 +706,21 @@ pub fn file_exists(path: String) -> ApiResponse<bool> {
     ApiResponse::success(Path::new(&path).exists())
 }
 
+pub const PATH_IS_FILE_CODE: &str = "path_is_file";
+
+/// Creates the directory and any missing parents; returns false when it already existed
 #[tauri::command]
-pub fn create_dir(path: String) -> ApiResponse<()> {
-    match fs::create_dir_all(&path) {
-        Ok(_) => ApiResponse::success(()),
+pub fn create_dir(path: String) -> ApiResponse<bool> {
+    let dir = Path::new(&path);
+    if dir.is_dir() {
+        return ApiResponse::success(false);
+    }
+    if dir.exists() {
+        return ApiResponse::error_with_code(PATH_IS_FILE_CODE, format!("A file already exists at {}", path));
+    }
+
+    match fs::create_dir_all(dir) {
+        Ok(_) => ApiResponse::success(true),
         Err(e) => ApiResponse::error(format!("Failed to create directory: {}", e)),
     }
 }
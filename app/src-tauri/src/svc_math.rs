@@ -0,0 +1,110 @@
+use crate::cache::{cache_dir, hash_hex};
+use crate::document::extract_preamble;
+use crate::project::ProjectConfig;
+use crate::svc_build::run_engine;
+use crate::svc_file::{read_base64, ApiResponse};
+use crate::svc_pdf::render_page_image;
+use std::path::Path;
+
+// Resolution of rendered formulas
+const MATH_RENDER_DPI: u32 = 200;
+
+// Used when the main file can't be read or has no \begin{document}
+const FALLBACK_PREAMBLE: &str = "\\documentclass{article}\n\\usepackage{amsmath,amssymb}\n";
+
+// The project's preamble (so custom macros work) with the preview package cropping
+// the page to the formula
+fn math_document(preamble: &str, snippet: &str, display: bool) -> String {
+    let math = if display {
+        format!("$\\displaystyle {}$", snippet)
+    } else {
+        format!("${}$", snippet)
+    };
+    format!(
+        "{}\n\\usepackage[active,tightpage]{{preview}}\n\\setlength\\PreviewBorder{{2pt}}\n\
+         \\begin{{document}}\n\\begin{{preview}}{}\\end{{preview}}\n\\end{{document}}\n",
+        preamble.trim_end(),
+        math
+    )
+}
+
+/// Render a formula with the project's preamble and engine, returned as a base64 PNG.
+/// Results are cached in `.easypaper/cache/math/` by a hash of the inputs.
+#[tauri::command]
+pub fn math_render(project_dir: String, latex_snippet: String, display: bool) -> ApiResponse<String> {
+    let snippet = latex_snippet.trim();
+    if snippet.is_empty() {
+        return ApiResponse::error("Formula is empty".to_string());
+    }
+
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    let main = std::fs::read_to_string(Path::new(&project_dir).join(&config.main)).unwrap_or_default();
+    let preamble = extract_preamble(&main).unwrap_or(FALLBACK_PREAMBLE);
+    let document = math_document(preamble, snippet, display);
+
+    let hash = hash_hex(format!("{}\n{}", config.engine.engine_type, document).as_bytes());
+    let math_dir = cache_dir(&project_dir).join("math");
+    let png = math_dir.join(format!("{}.png", &hash[..32]));
+    if png.is_file() {
+        return match read_base64(&png) {
+            Ok(Some(data)) => ApiResponse::success(data),
+            Ok(None) => ApiResponse::error("Rendered formula is too large".to_string()),
+            Err(e) => ApiResponse::error(e),
+        };
+    }
+    if let Err(e) = std::fs::create_dir_all(&math_dir) {
+        return ApiResponse::error(format!("Failed to create cache directory: {}", e));
+    }
+
+    // The snippet sits in the project root so the preamble's relative \input paths resolve;
+    // everything the engine writes goes to a temporary directory
+    let out_dir = match tempfile::Builder::new().prefix("easypaper-math-").tempdir() {
+        Ok(dir) => dir,
+        Err(e) => return ApiResponse::error(format!("Failed to create temporary build directory: {}", e)),
+    };
+    let source = format!(".math-{}.tex", &hash[..16]);
+    let source_path = Path::new(&project_dir).join(&source);
+    if let Err(e) = std::fs::write(&source_path, document) {
+        return ApiResponse::error(format!("Failed to write {}: {}", source, e));
+    }
+
+    let mut math_config = config.clone();
+    math_config.main = source;
+    math_config.compile.outdir = out_dir.path().to_string_lossy().to_string();
+    math_config.compile.synctex = false;
+    math_config.compile.shell_escape = false;
+    math_config.compile.precompile_preamble = false;
+    math_config.compile.passes = Some(1);
+
+    let result = run_engine(&project_dir, &math_config, std::time::Instant::now());
+    let _ = std::fs::remove_file(&source_path);
+    let build = match result {
+        Ok(build) => build,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let pdf = match (build.success, build.pdf_path) {
+        (true, Some(pdf)) => pdf,
+        _ => {
+            let message = build
+                .errors
+                .first()
+                .map(|e| e.message.clone())
+                .unwrap_or_else(|| "no PDF was produced".to_string());
+            return ApiResponse::error(format!("Failed to render formula: {}", message));
+        }
+    };
+
+    if let Err(e) = render_page_image(Path::new(&pdf), 1, MATH_RENDER_DPI, &png) {
+        return ApiResponse::error(e);
+    }
+    match read_base64(&png) {
+        Ok(Some(data)) => ApiResponse::success(data),
+        Ok(None) => ApiResponse::error("Rendered formula is too large".to_string()),
+        Err(e) => ApiResponse::error(e),
+    }
+}
@@ -0,0 +1,516 @@
+use crate::cache::hash_hex;
+use crate::svc_engine::find_in_path;
+use crate::svc_file::{read_base64, ApiResponse};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, State, Window};
+
+pub const PDF_UPDATED_EVENT: &str = "pdf://updated";
+
+// Quiet period after the last write before the PDF is considered settled
+const PDF_WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfUpdatedEvent {
+    pub path: String,
+}
+
+/// Active PDF watchers keyed by the watched file; dropping a watcher ends its thread
+#[derive(Default)]
+pub struct PdfWatchers {
+    watchers: Mutex<HashMap<PathBuf, RecommendedWatcher>>,
+}
+
+fn content_hash(path: &Path) -> Option<String> {
+    std::fs::read(path).ok().map(|bytes| hash_hex(&bytes))
+}
+
+// Watchers report canonical paths, so the file may not exist yet but its directory must
+fn canonical_pdf_path(pdf_path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(pdf_path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid PDF path: {}", pdf_path))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve PDF directory: {}", e))?;
+    Ok(parent.join(file_name))
+}
+
+/// Emit `pdf://updated` whenever the PDF's contents change, at most once per burst of writes
+#[tauri::command]
+pub fn pdf_watch_start(pdf_path: String, window: Window, watchers: State<'_, PdfWatchers>) -> ApiResponse<()> {
+    let pdf = match canonical_pdf_path(&pdf_path) {
+        Ok(pdf) => pdf,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let mut active = match watchers.watchers.lock() {
+        Ok(active) => active,
+        Err(e) => return ApiResponse::error(format!("Failed to access PDF watchers: {}", e)),
+    };
+    if active.contains_key(&pdf) {
+        return ApiResponse::success(());
+    }
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let watched = pdf.clone();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        if let Ok(event) = res {
+            if event.paths.contains(&watched) {
+                let _ = tx.send(());
+            }
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => return ApiResponse::error(format!("Failed to create PDF watcher: {}", e)),
+    };
+
+    // Watch the directory: engines often replace the PDF rather than rewrite it in place
+    let dir = pdf.parent().unwrap_or(Path::new("/")).to_path_buf();
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        return ApiResponse::error(format!("Failed to watch {}: {}", dir.display(), e));
+    }
+
+    let thread_pdf = pdf.clone();
+    std::thread::spawn(move || {
+        let mut last_hash = content_hash(&thread_pdf);
+
+        // Ends once the watcher, and with it the sender, is dropped
+        while rx.recv().is_ok() {
+            while rx.recv_timeout(PDF_WATCH_DEBOUNCE).is_ok() {}
+
+            let hash = content_hash(&thread_pdf);
+            if hash.is_some() && hash != last_hash {
+                last_hash = hash;
+                let _ = window.emit(
+                    PDF_UPDATED_EVENT,
+                    PdfUpdatedEvent {
+                        path: thread_pdf.to_string_lossy().to_string(),
+                    },
+                );
+            }
+        }
+    });
+
+    active.insert(pdf, watcher);
+    ApiResponse::success(())
+}
+
+#[tauri::command]
+pub fn pdf_watch_stop(pdf_path: String, watchers: State<'_, PdfWatchers>) -> ApiResponse<()> {
+    let pdf = match canonical_pdf_path(&pdf_path) {
+        Ok(pdf) => pdf,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    match watchers.watchers.lock() {
+        Ok(mut active) => {
+            active.remove(&pdf);
+            ApiResponse::success(())
+        }
+        Err(e) => ApiResponse::error(format!("Failed to access PDF watchers: {}", e)),
+    }
+}
+
+// Resolution range accepted by pdf_render_page
+const MIN_RENDER_DPI: u32 = 10;
+const MAX_RENDER_DPI: u32 = 600;
+
+// "Pages: N" as printed by both `pdfinfo` and `mutool info`
+fn pdf_page_count(pdf: &Path) -> Result<u32, String> {
+    let (program, args): (PathBuf, &[&str]) = if let Some(pdfinfo) = find_in_path("pdfinfo") {
+        (pdfinfo, &[])
+    } else if let Some(mutool) = find_in_path("mutool") {
+        (mutool, &["info"])
+    } else {
+        return Err("Rendering PDF pages requires poppler (pdftoppm) or mutool".to_string());
+    };
+
+    let output = Command::new(&program)
+        .args(args)
+        .arg(pdf)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to read PDF: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Pages:"))
+        .and_then(|count| count.trim().parse().ok())
+        .ok_or_else(|| "Failed to read the PDF's page count".to_string())
+}
+
+/// Writes page `page` (1-based) of `pdf` to `image` with pdftoppm or, when poppler is
+/// missing, mutool. The format follows the extension: .png, or .ppm for raw pixels.
+pub(crate) fn render_page_image(pdf: &Path, page: u32, dpi: u32, image: &Path) -> Result<(), String> {
+    let (page, dpi) = (page.to_string(), dpi.to_string());
+    let is_png = image.extension().is_some_and(|ext| ext == "png");
+
+    let mut cmd = if let Some(pdftoppm) = find_in_path("pdftoppm") {
+        // -singlefile writes <stem>.png / <stem>.ppm without a page-number suffix
+        let mut cmd = Command::new(pdftoppm);
+        if is_png {
+            cmd.arg("-png");
+        }
+        cmd.args(["-singlefile", "-r", &dpi, "-f", &page, "-l", &page])
+            .arg(pdf)
+            .arg(image.with_extension(""));
+        cmd
+    } else if let Some(mutool) = find_in_path("mutool") {
+        let mut cmd = Command::new(mutool);
+        cmd.args(["draw", "-q", "-r", &dpi, "-o"])
+            .arg(image)
+            .arg(pdf)
+            .arg(&page);
+        cmd
+    } else {
+        return Err("Rendering PDF pages requires poppler (pdftoppm) or mutool".to_string());
+    };
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run PDF renderer: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to render page: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Render one page (1-based) to PNG with pdftoppm or mutool, returned base64-encoded
+#[tauri::command]
+pub fn pdf_render_page(pdf_path: String, page: u32, dpi: u32) -> ApiResponse<String> {
+    let pdf = Path::new(&pdf_path);
+    if !pdf.is_file() {
+        return ApiResponse::error(format!("PDF not found: {}", pdf_path));
+    }
+    if !(MIN_RENDER_DPI..=MAX_RENDER_DPI).contains(&dpi) {
+        return ApiResponse::error(format!(
+            "dpi must be between {} and {}",
+            MIN_RENDER_DPI, MAX_RENDER_DPI
+        ));
+    }
+
+    let page_count = match pdf_page_count(pdf) {
+        Ok(count) => count,
+        Err(e) => return ApiResponse::error(e),
+    };
+    if page == 0 || page > page_count {
+        return ApiResponse::error(format!(
+            "Page {} is out of range (the PDF has {} pages)",
+            page, page_count
+        ));
+    }
+
+    // Each render gets its own output file so concurrent thumbnails don't collide
+    static NEXT_RENDER: AtomicU64 = AtomicU64::new(0);
+    let png = std::env::temp_dir().join(format!(
+        "easypaper-page-{}-{}.png",
+        std::process::id(),
+        NEXT_RENDER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = match render_page_image(pdf, page, dpi, &png) {
+        Ok(()) => match read_base64(&png) {
+            Ok(Some(data)) => ApiResponse::success(data),
+            Ok(None) => ApiResponse::error("Rendered page is too large; use a lower dpi".to_string()),
+            Err(e) => ApiResponse::error(e),
+        },
+        Err(e) => ApiResponse::error(e),
+    };
+
+    let _ = std::fs::remove_file(&png);
+    result
+}
+
+// Resolution pages are compared at; at 72 dpi one pixel is one PDF point
+const PDF_DIFF_DPI: u32 = 72;
+
+// Largest per-channel difference still treated as equal, to ignore antialiasing noise
+const PIXEL_TOLERANCE: u8 = 24;
+
+// Changed pixels are grouped into regions on a grid of cells this many pixels wide
+const REGION_CELL: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageDiff {
+    pub page: u32,
+    /// "unchanged", "changed", "added" or "removed"
+    pub status: String,
+    /// Bounding boxes of the changed areas, in PDF points from the top left
+    pub regions: Vec<DiffRegion>,
+    /// Base64 PNG of the new page with the changes highlighted, for changed pages
+    pub overlay: Option<String>,
+}
+
+// An RGB image, three bytes per pixel
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+// Binary (P6) PPM as written by pdftoppm and mutool
+fn read_ppm(path: &Path) -> Result<Raster, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read rendered page: {}", e))?;
+
+    // Header: magic, width, height and maxval separated by whitespace, with # comments
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while fields.len() < 4 && pos < bytes.len() {
+        match bytes[pos] {
+            b'#' => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => pos += 1,
+            _ => {
+                let start = pos;
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                fields.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+            }
+        }
+    }
+    // A single whitespace byte separates the header from the pixels
+    pos += 1;
+
+    let parse = |field: Option<&String>| field.and_then(|f| f.parse::<usize>().ok());
+    let (Some("P6"), Some(width), Some(height), Some(255)) = (
+        fields.first().map(String::as_str),
+        parse(fields.get(1)),
+        parse(fields.get(2)),
+        parse(fields.get(3)),
+    ) else {
+        return Err("Unsupported image from the PDF renderer".to_string());
+    };
+
+    let pixels = bytes
+        .get(pos..pos + width * height * 3)
+        .ok_or("Rendered page is truncated")?
+        .to_vec();
+    Ok(Raster { width, height, pixels })
+}
+
+// Changed pixels of two equally sized rasters, and the bounding boxes of connected changed cells
+fn changed_regions(old: &Raster, new: &Raster) -> (Vec<bool>, Vec<DiffRegion>) {
+    let changed: Vec<bool> = old
+        .pixels
+        .chunks(3)
+        .zip(new.pixels.chunks(3))
+        .map(|(a, b)| a.iter().zip(b).any(|(x, y)| x.abs_diff(*y) > PIXEL_TOLERANCE))
+        .collect();
+
+    let cols = new.width.div_ceil(REGION_CELL);
+    let rows = new.height.div_ceil(REGION_CELL);
+    let mut cells = vec![false; cols * rows];
+    for (i, _) in changed.iter().enumerate().filter(|(_, c)| **c) {
+        let (x, y) = (i % new.width, i / new.width);
+        cells[(y / REGION_CELL) * cols + x / REGION_CELL] = true;
+    }
+
+    // Flood fill over the cell grid; each component becomes one region
+    let mut regions = Vec::new();
+    let mut seen = vec![false; cells.len()];
+    for start in 0..cells.len() {
+        if !cells[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let (mut min_col, mut min_row, mut max_col, mut max_row) = (cols, rows, 0, 0);
+        while let Some(cell) = stack.pop() {
+            let (col, row) = (cell % cols, cell / cols);
+            min_col = min_col.min(col);
+            min_row = min_row.min(row);
+            max_col = max_col.max(col);
+            max_row = max_row.max(row);
+
+            let neighbours = [
+                (col > 0).then(|| cell - 1),
+                (col + 1 < cols).then(|| cell + 1),
+                (row > 0).then(|| cell - cols),
+                (row + 1 < rows).then(|| cell + cols),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if cells[next] && !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+
+        // Shrink the cell-aligned box to the changed pixels inside it
+        let xs = min_col * REGION_CELL..((max_col + 1) * REGION_CELL).min(new.width);
+        let ys = min_row * REGION_CELL..((max_row + 1) * REGION_CELL).min(new.height);
+        let (mut x0, mut y0, mut x1, mut y1) = (xs.end, ys.end, xs.start, ys.start);
+        for y in ys {
+            for x in xs.clone().filter(|x| changed[y * new.width + x]) {
+                x0 = x0.min(x);
+                y0 = y0.min(y);
+                x1 = x1.max(x);
+                y1 = y1.max(y);
+            }
+        }
+        regions.push(DiffRegion {
+            x: x0 as u32,
+            y: y0 as u32,
+            width: (x1 - x0 + 1) as u32,
+            height: (y1 - y0 + 1) as u32,
+        });
+    }
+
+    (changed, regions)
+}
+
+// The new page faded, with changed pixels in red and each region outlined, as a PNG
+fn diff_overlay(new: &Raster, changed: &[bool], regions: &[DiffRegion]) -> Result<String, String> {
+    const RED: [u8; 3] = [220, 30, 30];
+
+    let mut pixels: Vec<u8> = new.pixels.iter().map(|p| 255 - (255 - p) / 3).collect();
+    for (i, _) in changed.iter().enumerate().filter(|(_, c)| **c) {
+        pixels[i * 3..i * 3 + 3].copy_from_slice(&RED);
+    }
+
+    for region in regions {
+        let (x0, y0) = (region.x as usize, region.y as usize);
+        let (x1, y1) = (x0 + region.width as usize - 1, y0 + region.height as usize - 1);
+        for x in x0..=x1 {
+            for y in [y0, y1] {
+                let i = (y * new.width + x) * 3;
+                pixels[i..i + 3].copy_from_slice(&RED);
+            }
+        }
+        for y in y0..=y1 {
+            for x in [x0, x1] {
+                let i = (y * new.width + x) * 3;
+                pixels[i..i + 3].copy_from_slice(&RED);
+            }
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, new.width as u32, new.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| format!("Failed to encode diff image: {}", e))?;
+
+    Ok(BASE64.encode(png))
+}
+
+fn diff_page(old_pdf: &Path, new_pdf: &Path, page: u32, work_dir: &Path) -> Result<PageDiff, String> {
+    let old_image = work_dir.join(format!("old-{}.ppm", page));
+    let new_image = work_dir.join(format!("new-{}.ppm", page));
+    render_page_image(old_pdf, page, PDF_DIFF_DPI, &old_image)?;
+    render_page_image(new_pdf, page, PDF_DIFF_DPI, &new_image)?;
+    let old = read_ppm(&old_image)?;
+    let new = read_ppm(&new_image)?;
+    let _ = std::fs::remove_file(&old_image);
+    let _ = std::fs::remove_file(&new_image);
+
+    let (changed, regions) = if (old.width, old.height) == (new.width, new.height) {
+        changed_regions(&old, &new)
+    } else {
+        // A resized page counts as changed as a whole
+        let whole = DiffRegion {
+            x: 0,
+            y: 0,
+            width: new.width as u32,
+            height: new.height as u32,
+        };
+        (vec![false; new.width * new.height], vec![whole])
+    };
+
+    if regions.is_empty() {
+        return Ok(PageDiff {
+            page,
+            status: "unchanged".to_string(),
+            regions,
+            overlay: None,
+        });
+    }
+
+    Ok(PageDiff {
+        page,
+        status: "changed".to_string(),
+        overlay: Some(diff_overlay(&new, &changed, &regions)?),
+        regions,
+    })
+}
+
+fn diff_pdfs(old_pdf: &Path, new_pdf: &Path) -> Result<Vec<PageDiff>, String> {
+    let old_pages = pdf_page_count(old_pdf)?;
+    let new_pages = pdf_page_count(new_pdf)?;
+    let work_dir = tempfile::Builder::new()
+        .prefix("easypaper-diff-")
+        .tempdir()
+        .map_err(|e| format!("Failed to create temporary directory: {}", e))?;
+
+    (1..=old_pages.max(new_pages))
+        .map(|page| {
+            let status = if page > old_pages {
+                "added"
+            } else if page > new_pages {
+                "removed"
+            } else {
+                return diff_page(old_pdf, new_pdf, page, work_dir.path());
+            };
+            Ok(PageDiff {
+                page,
+                status: status.to_string(),
+                regions: vec![],
+                overlay: None,
+            })
+        })
+        .collect()
+}
+
+/// Compare two PDFs page by page, e.g. the previous and current build. Changed pages come
+/// with the changed regions and an overlay image; extra pages are reported as added or removed.
+#[tauri::command]
+pub fn pdf_diff(old_pdf: String, new_pdf: String) -> ApiResponse<Vec<PageDiff>> {
+    for pdf in [&old_pdf, &new_pdf] {
+        if !Path::new(pdf).is_file() {
+            return ApiResponse::error(format!("PDF not found: {}", pdf));
+        }
+    }
+
+    match diff_pdfs(Path::new(&old_pdf), Path::new(&new_pdf)) {
+        Ok(pages) => ApiResponse::success(pages),
+        Err(e) => ApiResponse::error(e),
+    }
+}
@@ -0,0 +1,560 @@
+use crate::cache::{read_json, unix_timestamp, write_json};
+use crate::document::{strip_comment, subfile_parent};
+use crate::project::{config_files, raw_config, ProjectConfig};
+use crate::svc_file::{is_ignored, ApiResponse};
+use crate::svc_template::GITIGNORE_TEMPLATE;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::State;
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone)]
+pub struct OpenProject {
+    pub root: PathBuf,
+    pub config: ProjectConfig,
+    // The config files as they were when `config` was read
+    stamp: ConfigStamp,
+}
+
+// Path, modification time and size of each config file
+type ConfigStamp = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+fn config_stamp(project_dir: &str) -> ConfigStamp {
+    config_files(project_dir)
+        .into_iter()
+        .map(|path| {
+            let meta = std::fs::metadata(&path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map(|m| m.len()).unwrap_or(0);
+            (path, modified, len)
+        })
+        .collect()
+}
+
+/// The currently open project, shared by all commands through Tauri-managed state
+#[derive(Default)]
+pub struct ProjectState {
+    current: Mutex<Option<OpenProject>>,
+}
+
+impl ProjectState {
+    /// Returns the cached config when `project_dir` is the open project, otherwise loads it from disk.
+    /// The cache is reloaded whenever a config file was added, removed or edited since it was read.
+    pub fn config_for(&self, project_dir: &str) -> Result<ProjectConfig, String> {
+        let Some(open) = self.current() else {
+            return ProjectConfig::load(project_dir);
+        };
+        if canonical_root(project_dir).ok().as_ref() != Some(&open.root) {
+            return ProjectConfig::load(project_dir);
+        }
+        // Taken before reading so an edit made meanwhile triggers another reload
+        let stamp = config_stamp(project_dir);
+        if stamp == open.stamp {
+            return Ok(open.config);
+        }
+
+        let config = ProjectConfig::load(project_dir)?;
+        self.replace_config(&open.root, &config, stamp);
+        Ok(config)
+    }
+
+    /// Replaces the cached config after it was written to disk, if `project_dir` is the open project
+    pub fn set_config(&self, project_dir: &str, config: &ProjectConfig) {
+        if let Ok(root) = canonical_root(project_dir) {
+            self.replace_config(&root, config, config_stamp(project_dir));
+        }
+    }
+
+    fn replace_config(&self, root: &Path, config: &ProjectConfig, stamp: ConfigStamp) {
+        if let Ok(mut current) = self.current.lock() {
+            if let Some(open) = current.as_mut().filter(|open| open.root == root) {
+                open.config = config.clone();
+                open.stamp = stamp;
+            }
+        }
+    }
+
+    pub fn current(&self) -> Option<OpenProject> {
+        self.current.lock().ok().and_then(|current| current.clone())
+    }
+}
+
+fn canonical_root(project_dir: &str) -> Result<PathBuf, String> {
+    Path::new(project_dir)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve project directory: {}", e))
+}
+
+/// Load and validate a project's config and make it the current project
+#[tauri::command]
+pub fn project_open(project_dir: String, state: State<'_, ProjectState>) -> ApiResponse<ProjectConfig> {
+    let root = match canonical_root(&project_dir) {
+        Ok(root) => root,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    if !root.is_dir() {
+        return ApiResponse::error(format!("Not a directory: {}", project_dir));
+    }
+
+    let stamp = config_stamp(&project_dir);
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    if let Err(e) = config.validate() {
+        return ApiResponse::error(format!("Invalid project config: {}", e));
+    }
+
+    match state.current.lock() {
+        Ok(mut current) => {
+            *current = Some(OpenProject {
+                root,
+                config: config.clone(),
+                stamp,
+            });
+            ApiResponse::success(config)
+        }
+        Err(e) => ApiResponse::error(format!("Failed to store project state: {}", e)),
+    }
+}
+
+/// Forget the current project
+#[tauri::command]
+pub fn project_close(state: State<'_, ProjectState>) -> ApiResponse<()> {
+    match state.current.lock() {
+        Ok(mut current) => {
+            *current = None;
+            ApiResponse::success(())
+        }
+        Err(e) => ApiResponse::error(format!("Failed to clear project state: {}", e)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrustRecord {
+    /// Canonical project directory the consent was given for
+    path: String,
+    trusted_at: u64,
+}
+
+fn trust_path(project_dir: &str) -> PathBuf {
+    PathBuf::from(project_dir).join(".easypaper").join("trust.json")
+}
+
+/// Whether the user allowed shell escape for this project. The record names the directory it
+/// was made in, so a trust file shipped inside a downloaded project does not count.
+pub fn is_trusted(project_dir: &str) -> bool {
+    let Ok(root) = canonical_root(project_dir) else {
+        return false;
+    };
+    match read_json::<TrustRecord>(&trust_path(project_dir)) {
+        Ok(Some(record)) => Path::new(&record.path) == root,
+        _ => false,
+    }
+}
+
+/// One setting of the effective configuration, e.g. `compile.synctex`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigSetting {
+    /// Dotted path as written in the config file
+    pub key: String,
+    pub value: serde_json::Value,
+    /// "file" when the config file sets it, "default" when it was filled in
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    /// The config file that was read; None when the project has none and only defaults apply
+    pub file: Option<String>,
+    pub settings: Vec<ConfigSetting>,
+}
+
+/// Every setting the project builds with, and whether it comes from the config file or a default.
+/// Optional settings that are unset and empty lists left at their default (e.g. `pre_build`) are omitted.
+#[tauri::command]
+pub fn project_config_effective(project_dir: String) -> ApiResponse<EffectiveConfig> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+    let (file, raw) = match raw_config(&project_dir) {
+        Ok(Some((path, raw))) => (Some(path.to_string_lossy().to_string()), raw),
+        Ok(None) => (None, serde_json::Value::Null),
+        Err(e) => return ApiResponse::error(e),
+    };
+    let effective = match serde_json::to_value(&config) {
+        Ok(value) => value,
+        Err(e) => return ApiResponse::error(format!("Failed to serialize project config: {}", e)),
+    };
+
+    let mut settings = Vec::new();
+    collect_settings(&effective, Some(&raw), "", &mut settings);
+    ApiResponse::success(EffectiveConfig { file, settings })
+}
+
+// Flattens `effective` into dotted keys; a setting is from the file when `raw` has the same path.
+// Lists count as single settings.
+fn collect_settings(
+    effective: &serde_json::Value,
+    raw: Option<&serde_json::Value>,
+    prefix: &str,
+    settings: &mut Vec<ConfigSetting>,
+) {
+    let serde_json::Value::Object(fields) = effective else {
+        settings.push(ConfigSetting {
+            key: prefix.to_string(),
+            value: effective.clone(),
+            source: if raw.is_some() { "file" } else { "default" }.to_string(),
+        });
+        return;
+    };
+
+    for (name, value) in fields {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        collect_settings(value, raw.and_then(|raw| raw.get(name)), &key, settings);
+    }
+}
+
+/// Record the user's consent to compile this project with shell escape enabled
+#[tauri::command]
+pub fn project_trust(project_dir: String) -> ApiResponse<()> {
+    let root = match canonical_root(&project_dir) {
+        Ok(root) => root,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let record = TrustRecord {
+        path: root.to_string_lossy().to_string(),
+        trusted_at: unix_timestamp(),
+    };
+
+    match write_json(&trust_path(&project_dir), &record) {
+        Ok(_) => ApiResponse::success(()),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+fn session_path(project_dir: &str) -> PathBuf {
+    PathBuf::from(project_dir).join(".easypaper").join("session.json")
+}
+
+/// Persist the editor session (open tabs, cursors, ...). The payload is opaque to the backend.
+#[tauri::command]
+pub fn session_save(project_dir: String, state: serde_json::Value) -> ApiResponse<()> {
+    let path = session_path(&project_dir);
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return ApiResponse::error(format!("Failed to create .easypaper directory: {}", e));
+        }
+    }
+
+    let content = match serde_json::to_string_pretty(&state) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to serialize session: {}", e)),
+    };
+
+    match std::fs::write(&path, content) {
+        Ok(_) => ApiResponse::success(()),
+        Err(e) => ApiResponse::error(format!("Failed to write session: {}", e)),
+    }
+}
+
+/// Load the saved editor session, or None if there isn't one
+#[tauri::command]
+pub fn session_load(project_dir: String) -> ApiResponse<Option<serde_json::Value>> {
+    let path = session_path(&project_dir);
+    if !path.exists() {
+        return ApiResponse::success(None);
+    }
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read session: {}", e)),
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(value) => ApiResponse::success(Some(value)),
+        Err(e) => ApiResponse::error(format!("Session file is not valid JSON: {}", e)),
+    }
+}
+
+/// Write EasyPaper's .gitignore if there is none; with `merge`, append the rules an
+/// existing .gitignore is missing. Returns the rules that were added.
+#[tauri::command]
+pub fn project_ensure_gitignore(project_dir: String, merge: bool) -> ApiResponse<Vec<String>> {
+    let path = Path::new(&project_dir).join(".gitignore");
+    let rules: Vec<String> = GITIGNORE_TEMPLATE
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+
+    if !path.exists() {
+        return match std::fs::write(&path, GITIGNORE_TEMPLATE) {
+            Ok(_) => ApiResponse::success(rules),
+            Err(e) => ApiResponse::error(format!("Failed to write .gitignore: {}", e)),
+        };
+    }
+
+    if !merge {
+        return ApiResponse::success(vec![]);
+    }
+
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read .gitignore: {}", e)),
+    };
+    let missing: Vec<String> = rules
+        .into_iter()
+        .filter(|rule| !existing.lines().any(|line| line.trim() == rule))
+        .collect();
+
+    if missing.is_empty() {
+        return ApiResponse::success(missing);
+    }
+
+    let mut merged = existing;
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    merged.push_str("\n# Added by EasyPaper\n");
+    for rule in &missing {
+        merged.push_str(rule);
+        merged.push('\n');
+    }
+
+    match std::fs::write(&path, merged) {
+        Ok(_) => ApiResponse::success(missing),
+        Err(e) => ApiResponse::error(format!("Failed to write .gitignore: {}", e)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectImport {
+    /// The created (or already existing) config; None when the main file is ambiguous
+    pub config: Option<ProjectConfig>,
+    /// Files that look like a main document, relative to the project
+    pub candidates: Vec<String>,
+}
+
+// Packages that only compile under LuaLaTeX
+const LUA_PACKAGES: &[&str] = &["luacode", "luatexja", "luatexbase", "luaotfload", "lua-visual-debug"];
+
+// Standalone documents: a \documentclass and a \begin{document}, but not a subfiles part
+fn find_main_candidates(project_dir: &str) -> Vec<String> {
+    let walker = WalkDir::new(project_dir).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() == 0 || !name.starts_with('.') && name != "out"
+    });
+
+    let mut candidates: Vec<String> = walker
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "tex"))
+        .filter(|entry| {
+            let Ok(content) = std::fs::read_to_string(entry.path()) else {
+                return false;
+            };
+            let code: String = content.lines().map(strip_comment).collect::<Vec<_>>().join("\n");
+            code.contains("\\documentclass") && code.contains("\\begin{document}") && subfile_parent(&code).is_none()
+        })
+        .map(|entry| {
+            entry
+                .path()
+                .strip_prefix(project_dir)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+
+    candidates.sort();
+    candidates
+}
+
+// tectonic is XeTeX-based and covers fontspec and friends; LuaLaTeX-only documents need latexmk
+fn infer_engine(project_dir: &str, config: &mut ProjectConfig) {
+    let content = std::fs::read_to_string(Path::new(project_dir).join(&config.main)).unwrap_or_default();
+    let needs_lua = content.lines().map(strip_comment).any(|line| {
+        line.contains("\\directlua")
+            || (line.contains("\\usepackage") && LUA_PACKAGES.iter().any(|pkg| line.contains(pkg)))
+    });
+
+    if needs_lua {
+        config.engine.engine_type = "latexmk".to_string();
+        config.engine.args = vec!["-lualatex".to_string()];
+    } else if Path::new(project_dir).join("latexmkrc").exists() || Path::new(project_dir).join(".latexmkrc").exists() {
+        // The author already drives the build through latexmk
+        config.engine.engine_type = "latexmk".to_string();
+    }
+}
+
+/// Create `.easypaper/project.yml` (unless a config already exists) for a folder of existing .tex files. The main file is
+/// detected unless given; when several files qualify, they are returned for the user to pick.
+#[tauri::command]
+pub fn project_import(project_dir: String, main: Option<String>) -> ApiResponse<ProjectImport> {
+    if !config_files(&project_dir).is_empty() {
+        return match ProjectConfig::load(&project_dir) {
+            Ok(config) => ApiResponse::success(ProjectImport {
+                config: Some(config),
+                candidates: vec![],
+            }),
+            Err(e) => ApiResponse::error(format!("Failed to load project config: {}", e)),
+        };
+    }
+
+    let candidates = find_main_candidates(&project_dir);
+    let main = match main {
+        Some(main) => main,
+        None => {
+            // A single candidate at the top level wins over drafts in subdirectories
+            let top_level: Vec<&String> = candidates.iter().filter(|c| !c.contains('/')).collect();
+            match (candidates.len(), top_level.as_slice()) {
+                (0, _) => return ApiResponse::error("No main .tex file found".to_string()),
+                (1, _) => candidates[0].clone(),
+                (_, [only]) => only.to_string(),
+                _ => {
+                    return ApiResponse::success(ProjectImport {
+                        config: None,
+                        candidates,
+                    })
+                }
+            }
+        }
+    };
+
+    if !Path::new(&project_dir).join(&main).is_file() {
+        return ApiResponse::error(format!("Main file '{}' not found", main));
+    }
+
+    let mut config = ProjectConfig {
+        name: Path::new(&project_dir)
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| ProjectConfig::default().name),
+        main,
+        ..Default::default()
+    };
+    infer_engine(&project_dir, &mut config);
+
+    if let Err(e) = config.save(&project_dir) {
+        return ApiResponse::error(e);
+    }
+
+    ApiResponse::success(ProjectImport {
+        config: Some(config),
+        candidates,
+    })
+}
+
+/// Zip the project sources for submission, leaving out build output, VCS and editor files.
+/// Returns the archive size in bytes.
+#[tauri::command]
+pub fn project_archive(project_dir: String, dest_zip: String, include_pdf: bool) -> ApiResponse<u64> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    // The template's rules, except *.pdf: figures are often PDFs
+    let mut rules: Vec<&str> = GITIGNORE_TEMPLATE
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && *line != "*.pdf")
+        .collect();
+    rules.extend([".git/", ".easypaper/"]);
+
+    let root = std::path::absolute(&project_dir).unwrap_or_else(|_| PathBuf::from(&project_dir));
+    let out_dir = config.out_dir(&project_dir);
+    let dest = std::path::absolute(&dest_zip).unwrap_or_else(|_| PathBuf::from(&dest_zip));
+
+    // Checked up front so a missing PDF never leaves an archive behind
+    let pdf = if include_pdf {
+        let pdf_path = config.output_path(&project_dir, "pdf");
+        let Some(pdf_name) = pdf_path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            return ApiResponse::error("Invalid PDF path".to_string());
+        };
+        if !pdf_path.exists() {
+            return ApiResponse::error("No compiled PDF found. Compile the project first.".to_string());
+        }
+        Some((pdf_path, pdf_name))
+    } else {
+        None
+    };
+
+    // Written next to the destination and renamed into place, so a failure leaves no partial zip
+    let Some(file_name) = dest.file_name() else {
+        return ApiResponse::error(format!("Invalid archive path: {}", dest_zip));
+    };
+    let tmp_path = dest.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+    let written = write_archive(&root, &out_dir, &rules, &tmp_path, &dest, pdf.as_ref())
+        .and_then(|_| std::fs::rename(&tmp_path, &dest).map_err(|e| format!("Failed to write archive: {}", e)));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp_path);
+        return ApiResponse::error(e);
+    }
+
+    match std::fs::metadata(&dest) {
+        Ok(meta) => ApiResponse::success(meta.len()),
+        Err(e) => ApiResponse::error(format!("Failed to read archive size: {}", e)),
+    }
+}
+
+// Zips the project files `rules` keep, plus the PDF at the top level when given, into `tmp_path`
+fn write_archive(
+    root: &Path,
+    out_dir: &Path,
+    rules: &[&str],
+    tmp_path: &Path,
+    dest: &Path,
+    pdf: Option<&(PathBuf, String)>,
+) -> Result<(), String> {
+    let file = std::fs::File::create(tmp_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+
+    let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+        let rel_path = relative(entry.path());
+        entry.depth() == 0
+            || (entry.path() != out_dir && !is_ignored(rules, &rel_path, entry.file_type().is_dir()))
+    });
+
+    let mut add_file = |path: &Path, name: &str| -> Result<(), String> {
+        let content = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        zip.start_file(name, options)
+            .and_then(|_| zip.write_all(&content).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to archive: {}", name, e))
+    };
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() || entry.path() == dest || entry.path() == tmp_path {
+            continue;
+        }
+        let name = relative(entry.path());
+        // The compiled PDF takes this name; a source file with the same name would duplicate it
+        if pdf.is_some_and(|(_, pdf_name)| *pdf_name == name) {
+            continue;
+        }
+        add_file(entry.path(), &name)?;
+    }
+
+    if let Some((pdf_path, pdf_name)) = pdf {
+        add_file(pdf_path, pdf_name)?;
+    }
+
+    zip.finish().map_err(|e| format!("Failed to write archive: {}", e))?;
+    Ok(())
+}
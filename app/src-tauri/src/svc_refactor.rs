@@ -0,0 +1,156 @@
+use crate::document::strip_comment;
+use crate::svc_file::{write_atomic, ApiResponse};
+use crate::svc_search::{project_entries, read_text_file};
+use crate::svc_spell::VERBATIM_ENVS;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelEdit {
+    pub file: String,
+    pub edits: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameResult {
+    pub files: Vec<LabelEdit>,
+    pub total_edits: usize,
+}
+
+// \label and the reference commands of LaTeX, hyperref, cleveref and varioref;
+// the argument may be a comma-separated list (\cref{a,b})
+fn label_command_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"\\(label|ref|eqref|pageref|autoref|nameref|cref|Cref|cpageref|Cpageref|crefrange|Crefrange|labelcref|vref|Vref|vpageref)\*?\s*(?:\[[^\]]*\])?\s*\{([^}]*)\}",
+        )
+        .unwrap()
+    })
+}
+
+struct SourceFile {
+    path: PathBuf,
+    rel_path: String,
+    content: String,
+}
+
+/// Byte ranges of `label` inside label/reference arguments, skipping comments and
+/// verbatim environments. Also reports whether the file defines the label.
+fn label_occurrences(re: &Regex, content: &str, label: &str) -> (Vec<(usize, usize)>, bool) {
+    let mut ranges = Vec::new();
+    let mut defined = false;
+    let mut verbatim_env: Option<String> = None;
+    let mut line_start = 0;
+
+    for line in content.split_inclusive('\n') {
+        let offset = line_start;
+        line_start += line.len();
+
+        if let Some(env) = &verbatim_env {
+            if line.contains(&format!("\\end{{{}}}", env)) {
+                verbatim_env = None;
+            }
+            continue;
+        }
+
+        let code = strip_comment(line);
+        for caps in re.captures_iter(code) {
+            let args = caps.get(2).unwrap();
+            let mut item_start = args.start();
+            for item in args.as_str().split(',') {
+                let trimmed = item.trim();
+                if trimmed == label {
+                    let start = offset + item_start + (item.len() - item.trim_start().len());
+                    ranges.push((start, start + trimmed.len()));
+                    defined |= &caps[1] == "label";
+                }
+                item_start += item.len() + 1;
+            }
+        }
+
+        verbatim_env = VERBATIM_ENVS
+            .iter()
+            .find(|env| {
+                code.contains(&format!("\\begin{{{}}}", env)) && !code.contains(&format!("\\end{{{}}}", env))
+            })
+            .map(|env| env.to_string());
+    }
+
+    (ranges, defined)
+}
+
+/// Rename a label and every reference to it across the project's .tex files.
+/// All edits are worked out before any file is written, each through an atomic replace.
+#[tauri::command]
+pub fn refactor_rename_label(project_dir: String, old_label: String, new_label: String) -> ApiResponse<RenameResult> {
+    let (old_label, new_label) = (old_label.trim(), new_label.trim());
+    if new_label.is_empty() || new_label.contains([',', '{', '}', '%']) {
+        return ApiResponse::error(format!("Invalid label name: '{}'", new_label));
+    }
+    if old_label == new_label {
+        return ApiResponse::success(RenameResult {
+            files: vec![],
+            total_edits: 0,
+        });
+    }
+
+    let sources: Vec<SourceFile> = project_entries(&project_dir)
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "tex"))
+        .filter_map(|entry| {
+            let content = read_text_file(entry.path())?;
+            let rel_path = entry
+                .path()
+                .strip_prefix(&project_dir)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .to_string();
+            Some(SourceFile {
+                path: entry.path().to_path_buf(),
+                rel_path,
+                content,
+            })
+        })
+        .collect();
+
+    let re = label_command_regex();
+    if sources.iter().any(|src| label_occurrences(re, &src.content, new_label).1) {
+        return ApiResponse::error(format!("Label '{}' is already defined", new_label));
+    }
+
+    let mut old_defined = false;
+    let mut updates = Vec::new();
+    for src in &sources {
+        let (ranges, defined) = label_occurrences(re, &src.content, old_label);
+        old_defined |= defined;
+        if ranges.is_empty() {
+            continue;
+        }
+
+        let mut updated = src.content.clone();
+        for (start, end) in ranges.iter().rev() {
+            updated.replace_range(start..end, new_label);
+        }
+        updates.push((src, updated, ranges.len()));
+    }
+
+    if !old_defined {
+        return ApiResponse::error(format!("Label '{}' is not defined", old_label));
+    }
+
+    let mut files = Vec::new();
+    for (src, updated, edits) in updates {
+        if let Err(e) = write_atomic(&src.path, &updated) {
+            return ApiResponse::error(format!("Failed to update {}: {}", src.rel_path, e));
+        }
+        files.push(LabelEdit {
+            file: src.rel_path.clone(),
+            edits,
+        });
+    }
+
+    let total_edits = files.iter().map(|f| f.edits).sum();
+    ApiResponse::success(RenameResult { files, total_edits })
+}
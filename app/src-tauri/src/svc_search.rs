@@ -0,0 +1,175 @@
+use crate::project::ProjectConfig;
+use crate::svc_diff::{diff_hunks, DiffHunk};
+use crate::svc_file::{write_atomic, ApiResponse};
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of literal text
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    /// Report the changes without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReplacement {
+    pub file: String,
+    pub replacements: usize,
+    /// Would-be changes, only filled in for dry runs
+    pub hunks: Option<Vec<DiffHunk>>,
+}
+
+// Directories never touched by project-wide edits, besides the build output directory
+const SKIPPED_DIRS: &[&str] = &[".git", ".easypaper", "node_modules"];
+
+/// Everything under `dir` that project-wide edits may touch: not SKIPPED_DIRS and not
+/// the project's configured output directory
+pub(crate) fn project_entries(dir: &str) -> impl Iterator<Item = DirEntry> {
+    let out_dir = ProjectConfig::load(dir).unwrap_or_default().out_dir(dir);
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(move |entry| {
+            !entry.file_type().is_dir()
+                || !(SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+                    || std::path::absolute(entry.path()).is_ok_and(|path| path == out_dir))
+        })
+        .filter_map(|e| e.ok())
+}
+
+fn build_query_regex(query: &str, opts: &SearchOptions) -> Result<Regex, String> {
+    let pattern = if opts.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!opts.case_sensitive)
+        .multi_line(true)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+// \b does not work for queries like `\newcommand` that start or end with a non-word character
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    !is_word_char(text[..start].chars().next_back()) && !is_word_char(text[end..].chars().next())
+}
+
+/// Returns the new text and the number of replacements made
+fn replace_all(text: &str, re: &Regex, replacement: &str, opts: &SearchOptions) -> (String, usize) {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut count = 0;
+
+    for caps in re.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        if whole.as_str().is_empty() || (opts.whole_word && !is_whole_word(text, whole.start(), whole.end())) {
+            continue;
+        }
+
+        result.push_str(&text[last..whole.start()]);
+        expand_replacement(&caps, replacement, opts, &mut result);
+        last = whole.end();
+        count += 1;
+    }
+
+    result.push_str(&text[last..]);
+    (result, count)
+}
+
+fn expand_replacement(caps: &Captures, replacement: &str, opts: &SearchOptions, out: &mut String) {
+    // Only regex mode understands $1-style group references
+    if opts.regex {
+        caps.expand(replacement, out);
+    } else {
+        out.push_str(replacement);
+    }
+}
+
+// Text files only; a NUL byte in the first few KiB means binary
+pub(crate) fn read_text_file(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.iter().take(8192).any(|b| *b == 0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[tauri::command]
+pub fn file_replace(
+    dir: String,
+    query: String,
+    replacement: String,
+    opts: Option<SearchOptions>,
+) -> ApiResponse<Vec<FileReplacement>> {
+    let opts = opts.unwrap_or_default();
+    if query.is_empty() {
+        return ApiResponse::error("Search query must not be empty".to_string());
+    }
+
+    let re = match build_query_regex(&query, &opts) {
+        Ok(re) => re,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    // Work out every edit before writing so a bad file doesn't leave the project half-replaced
+    let mut edits = Vec::new();
+    for entry in project_entries(&dir) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Some(content) = read_text_file(entry.path()) else {
+            continue;
+        };
+
+        let (updated, replacements) = replace_all(&content, &re, &replacement, &opts);
+        if replacements == 0 {
+            continue;
+        }
+
+        let file = entry
+            .path()
+            .strip_prefix(&dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .to_string();
+        edits.push((entry.into_path(), file, content, updated, replacements));
+    }
+
+    let mut results: Vec<FileReplacement> = Vec::new();
+    for (path, file, content, updated, replacements) in edits {
+        let hunks = if opts.dry_run {
+            Some(diff_hunks(&content, &updated))
+        } else {
+            if let Err(e) = write_atomic(&path, &updated) {
+                let written: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+                return ApiResponse::error(match written.is_empty() {
+                    true => format!("Failed to update {}: {}", file, e),
+                    false => format!("Failed to update {}: {}; already updated: {}", file, e, written.join(", ")),
+                });
+            }
+            None
+        };
+
+        results.push(FileReplacement {
+            file,
+            replacements,
+            hunks,
+        });
+    }
+
+    ApiResponse::success(results)
+}
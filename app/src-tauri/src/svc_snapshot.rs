@@ -0,0 +1,157 @@
+use crate::cache::{read_json, snapshot_sources, unix_timestamp, write_json, SourceSnapshot};
+use crate::project::ProjectConfig;
+use crate::svc_file::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Directory name under `.easypaper/snapshots/`
+    pub id: String,
+    pub label: Option<String>,
+    pub created_at: u64,
+    pub files: usize,
+}
+
+// `manifest.json` inside each snapshot directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotManifest {
+    label: Option<String>,
+    created_at: u64,
+    files: SourceSnapshot,
+}
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+fn snapshots_dir(project_dir: &str) -> PathBuf {
+    Path::new(project_dir).join(".easypaper").join("snapshots")
+}
+
+// Copies the project's source files into a new snapshot directory
+fn create_snapshot(project_dir: &str, config: &ProjectConfig, label: Option<String>) -> Result<Snapshot, String> {
+    // snapshot_sources skips .easypaper, so earlier snapshots are never copied
+    let files = snapshot_sources(project_dir, &config.out_dir(project_dir), None);
+    let created_at = unix_timestamp();
+
+    // Several snapshots in the same second get a numeric suffix
+    let root = snapshots_dir(project_dir);
+    let mut id = created_at.to_string();
+    let mut n = 1;
+    while root.join(&id).exists() {
+        n += 1;
+        id = format!("{}-{}", created_at, n);
+    }
+    let dir = root.join(&id);
+
+    for rel_path in files.keys() {
+        let target = dir.join(rel_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+        }
+        fs::copy(Path::new(project_dir).join(rel_path), &target)
+            .map_err(|e| format!("Failed to copy {}: {}", rel_path, e))?;
+    }
+
+    let manifest = SnapshotManifest {
+        label: label.clone(),
+        created_at,
+        files,
+    };
+    write_json(&dir.join(MANIFEST_FILE), &manifest)?;
+
+    Ok(Snapshot {
+        id,
+        label,
+        created_at,
+        files: manifest.files.len(),
+    })
+}
+
+/// Saves a copy of the project's source files under `.easypaper/snapshots/<timestamp>/`
+#[tauri::command]
+pub fn snapshot_create(project_dir: String, label: Option<String>) -> ApiResponse<Snapshot> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    match create_snapshot(&project_dir, &config, label) {
+        Ok(snapshot) => ApiResponse::success(snapshot),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+/// Available snapshots, newest first
+#[tauri::command]
+pub fn snapshot_list(project_dir: String) -> ApiResponse<Vec<Snapshot>> {
+    let entries = match fs::read_dir(snapshots_dir(&project_dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return ApiResponse::success(Vec::new()),
+        Err(e) => return ApiResponse::error(format!("Failed to read snapshots: {}", e)),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        // Directories without a readable manifest are incomplete snapshots
+        let Ok(Some(manifest)) = read_json::<SnapshotManifest>(&entry.path().join(MANIFEST_FILE)) else {
+            continue;
+        };
+        snapshots.push(Snapshot {
+            id: entry.file_name().to_string_lossy().to_string(),
+            label: manifest.label,
+            created_at: manifest.created_at,
+            files: manifest.files.len(),
+        });
+    }
+
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    ApiResponse::success(snapshots)
+}
+
+/// Restores the source files of snapshot `id`. The current state is snapshotted first,
+/// and that backup is returned so the restore can be undone.
+#[tauri::command]
+pub fn snapshot_restore(project_dir: String, id: String) -> ApiResponse<Snapshot> {
+    let config = match ProjectConfig::load(&project_dir) {
+        Ok(cfg) => cfg,
+        Err(e) => return ApiResponse::error(format!("Failed to load project config: {}", e)),
+    };
+
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return ApiResponse::error(format!("Invalid snapshot id: {}", id));
+    }
+    let dir = snapshots_dir(&project_dir).join(&id);
+    let manifest: SnapshotManifest = match read_json(&dir.join(MANIFEST_FILE)) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => return ApiResponse::error(format!("Snapshot not found: {}", id)),
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let backup = match create_snapshot(&project_dir, &config, Some(format!("Before restoring {}", id))) {
+        Ok(backup) => backup,
+        Err(e) => return ApiResponse::error(format!("Failed to back up current state: {}", e)),
+    };
+
+    // Source files added since the snapshot are removed; they are kept in the backup
+    let current = snapshot_sources(&project_dir, &config.out_dir(&project_dir), None);
+    for rel_path in current.keys().filter(|p| !manifest.files.contains_key(*p)) {
+        if let Err(e) = fs::remove_file(Path::new(&project_dir).join(rel_path)) {
+            return ApiResponse::error(format!("Failed to remove {}: {}", rel_path, e));
+        }
+    }
+
+    for rel_path in manifest.files.keys() {
+        let target = Path::new(&project_dir).join(rel_path);
+        if let Some(parent) = target.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return ApiResponse::error(format!("Failed to create directory for {}: {}", rel_path, e));
+            }
+        }
+        if let Err(e) = fs::copy(dir.join(rel_path), &target) {
+            return ApiResponse::error(format!("Failed to restore {}: {}", rel_path, e));
+        }
+    }
+
+    ApiResponse::success(backup)
+}
@@ -0,0 +1,368 @@
+use crate::svc_file::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpellIssue {
+    pub line: u32,
+    pub column: u32,
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+// Environments whose content is never prose
+pub(crate) const VERBATIM_ENVS: &[&str] = &["verbatim", "verbatim*", "Verbatim", "lstlisting", "minted", "comment"];
+
+const MATH_ENVS: &[&str] = &[
+    "equation", "equation*", "align", "align*", "gather", "gather*", "multline", "multline*",
+    "eqnarray", "eqnarray*", "displaymath", "math", "flalign", "flalign*",
+];
+
+// Commands whose braced argument is a key, path or name rather than prose
+const SKIP_ARG_COMMANDS: &[&str] = &[
+    "label", "ref", "eqref", "autoref", "cref", "Cref", "pageref", "nameref", "cite", "citep",
+    "citet", "citeauthor", "citeyear", "nocite", "input", "include", "includegraphics",
+    "usepackage", "RequirePackage", "documentclass", "bibliography", "bibliographystyle",
+    "addbibresource", "url", "href", "graphicspath", "hypersetup", "begin", "end",
+    "newcommand", "renewcommand", "newenvironment", "renewenvironment", "setlength",
+    "setcounter", "pagestyle", "thispagestyle",
+];
+
+/// Extracts spell-checkable words from LaTeX source, one line at a time.
+/// Keeps track of verbatim and math blocks that span several lines.
+#[derive(Default)]
+pub(crate) struct ProseScanner {
+    verbatim_env: Option<String>,
+    math_env: Option<String>,
+    display_math: bool,
+}
+
+impl ProseScanner {
+    /// Returns the prose words in `line` with their 1-based column (in characters)
+    pub(crate) fn words(&mut self, line: &str) -> Vec<(u32, String)> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut words = Vec::new();
+        let mut i = 0;
+
+        // Still inside a verbatim block: only look for its end
+        if let Some(env) = &self.verbatim_env {
+            let end = format!("\\end{{{}}}", env);
+            match line.find(&end) {
+                Some(pos) => {
+                    i = line[..pos + end.len()].chars().count();
+                    self.verbatim_env = None;
+                }
+                None => return words,
+            }
+        }
+
+        let mut word = String::new();
+        let mut word_start = 0;
+        let mut inline_math = false;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let in_math = inline_math || self.display_math || self.math_env.is_some();
+
+            if c.is_alphabetic() && !in_math {
+                if word.is_empty() {
+                    word_start = i;
+                }
+                word.push(c);
+                i += 1;
+                continue;
+            }
+
+            // Allow apostrophes inside words ("don't")
+            if c == '\'' && !word.is_empty() && chars.get(i + 1).is_some_and(|n| n.is_alphabetic()) {
+                word.push(c);
+                i += 1;
+                continue;
+            }
+
+            flush_word(&mut word, word_start, &mut words);
+
+            match c {
+                '%' => break,
+                '$' => {
+                    if chars.get(i + 1) == Some(&'$') {
+                        self.display_math = !self.display_math;
+                        i += 2;
+                    } else {
+                        inline_math = !inline_math;
+                        i += 1;
+                    }
+                }
+                '\\' => {
+                    i = self.skip_command(&chars, i, &mut inline_math);
+                    if self.verbatim_env.is_some() {
+                        // The block may close again on the same line
+                        let rest: String = chars[i..].iter().collect();
+                        let env = self.verbatim_env.clone().unwrap_or_default();
+                        let end = format!("\\end{{{}}}", env);
+                        match rest.find(&end) {
+                            Some(pos) => {
+                                i += rest[..pos + end.len()].chars().count();
+                                self.verbatim_env = None;
+                            }
+                            None => break,
+                        }
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+
+        flush_word(&mut word, word_start, &mut words);
+        words
+    }
+
+    // Consumes a control sequence starting at `start` (the backslash) and returns the next index
+    fn skip_command(&mut self, chars: &[char], start: usize, inline_math: &mut bool) -> usize {
+        let mut i = start + 1;
+        let Some(&next) = chars.get(i) else {
+            return i;
+        };
+
+        if !next.is_ascii_alphabetic() {
+            match next {
+                '(' => *inline_math = true,
+                ')' => *inline_math = false,
+                '[' => self.display_math = true,
+                ']' => self.display_math = false,
+                _ => {}
+            }
+            return i + 1;
+        }
+
+        let name_start = i;
+        while i < chars.len() && chars[i].is_ascii_alphabetic() {
+            i += 1;
+        }
+        let name: String = chars[name_start..i].iter().collect();
+        if chars.get(i) == Some(&'*') {
+            i += 1;
+        }
+
+        match name.as_str() {
+            "verb" => {
+                // \verb|...| uses an arbitrary delimiter
+                if let Some(&delim) = chars.get(i) {
+                    i += 1;
+                    while i < chars.len() && chars[i] != delim {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i
+            }
+            "begin" | "end" => {
+                let (env, next) = read_group(chars, i);
+                if let Some(env) = env {
+                    if name == "begin" {
+                        if VERBATIM_ENVS.contains(&env.as_str()) {
+                            self.verbatim_env = Some(env);
+                        } else if MATH_ENVS.contains(&env.as_str()) {
+                            self.math_env = Some(env);
+                        }
+                    } else if self.math_env.as_deref() == Some(env.as_str()) {
+                        self.math_env = None;
+                    }
+                }
+                next
+            }
+            _ if SKIP_ARG_COMMANDS.contains(&name.as_str()) => {
+                // Skip optional arguments, then the first mandatory one
+                loop {
+                    while i < chars.len() && chars[i] == ' ' {
+                        i += 1;
+                    }
+                    if chars.get(i) == Some(&'[') {
+                        i = skip_balanced(chars, i, '[', ']');
+                    } else {
+                        break;
+                    }
+                }
+                let (_, next) = read_group(chars, i);
+                next
+            }
+            _ => i,
+        }
+    }
+}
+
+fn flush_word(word: &mut String, start: usize, words: &mut Vec<(u32, String)>) {
+    if word.is_empty() {
+        return;
+    }
+    let w = std::mem::take(word);
+    // Single letters and all-caps acronyms are not worth flagging
+    let is_acronym = w.chars().count() > 1 && w.chars().all(|c| !c.is_lowercase());
+    if w.chars().count() > 1 && !is_acronym {
+        words.push((start as u32 + 1, w));
+    }
+}
+
+// Reads a `{...}` group at `i`, returning its content and the index after it
+fn read_group(chars: &[char], i: usize) -> (Option<String>, usize) {
+    if chars.get(i) != Some(&'{') {
+        return (None, i);
+    }
+    let end = skip_balanced(chars, i, '{', '}');
+    let content: String = chars[i + 1..end.saturating_sub(1).max(i + 1)].iter().collect();
+    (Some(content), end)
+}
+
+fn skip_balanced(chars: &[char], start: usize, open: char, close: char) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == open {
+            depth += 1;
+        } else if chars[i] == close {
+            depth -= 1;
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Spell-check the prose of a LaTeX file using hunspell or aspell
+#[tauri::command]
+pub fn spell_check(path: String, lang: Option<String>) -> ApiResponse<Vec<SpellIssue>> {
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return ApiResponse::error(format!("Failed to read file: {}", e)),
+    };
+
+    let lang = lang.unwrap_or_else(|| "en_US".to_string());
+
+    // Collect every word occurrence, then check each distinct word once
+    let mut scanner = ProseScanner::default();
+    let mut occurrences = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        for (column, word) in scanner.words(line) {
+            occurrences.push((idx as u32 + 1, column, word));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let unique: Vec<String> = occurrences
+        .iter()
+        .filter(|(_, _, w)| seen.insert(w.clone()))
+        .map(|(_, _, w)| w.clone())
+        .collect();
+
+    let misspelled = match check_words(&unique, &lang) {
+        Ok(m) => m,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let issues = occurrences
+        .into_iter()
+        .filter_map(|(line, column, word)| {
+            misspelled.get(&word).map(|suggestions| SpellIssue {
+                line,
+                column,
+                suggestions: suggestions.clone(),
+                word,
+            })
+        })
+        .collect();
+
+    ApiResponse::success(issues)
+}
+
+// Runs the words through the first available ispell-compatible checker
+fn check_words(words: &[String], lang: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    if words.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let checkers: [(&str, Vec<String>); 2] = [
+        ("hunspell", vec!["-a".to_string(), "-d".to_string(), lang.to_string()]),
+        ("aspell", vec!["-a".to_string(), format!("--lang={}", lang)]),
+    ];
+
+    for (bin, args) in checkers.iter() {
+        let child = Command::new(bin)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to run {}: {}", bin, e)),
+        };
+
+        // One word per line, prefixed with '^' so nothing is read as a pipe command.
+        // Written from a separate thread so a full stdout pipe can't deadlock us.
+        let input: String = words.iter().map(|w| format!("^{}\n", w)).collect();
+        let mut stdin = child.stdin.take().ok_or("Failed to open spell checker stdin")?;
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            out.read_to_string(&mut stdout)
+                .map_err(|e| format!("Failed to read {} output: {}", bin, e))?;
+        }
+        let _ = writer.join();
+
+        let status = child.wait().map_err(|e| format!("Failed to run {}: {}", bin, e))?;
+        if !status.success() {
+            let mut stderr = String::new();
+            if let Some(mut err) = child.stderr.take() {
+                let _ = err.read_to_string(&mut stderr);
+            }
+            return Err(format!("{} failed: {}", bin, stderr.trim()));
+        }
+
+        return Ok(parse_ispell_output(&stdout, words));
+    }
+
+    Err("No spell checker found. Please install hunspell or aspell.".to_string())
+}
+
+// Parses ispell pipe-mode output: one result group (terminated by a blank line) per input line
+fn parse_ispell_output(output: &str, words: &[String]) -> HashMap<String, Vec<String>> {
+    let mut misspelled = HashMap::new();
+    let mut index = 0;
+
+    for line in output.lines() {
+        if line.starts_with("@(#)") {
+            continue;
+        }
+        if line.is_empty() {
+            index += 1;
+            continue;
+        }
+        let Some(word) = words.get(index) else {
+            break;
+        };
+
+        if let Some(rest) = line.strip_prefix("& ") {
+            // & <word> <count> <offset>: <suggestion>, <suggestion>, ...
+            let suggestions = rest
+                .split_once(": ")
+                .map(|(_, s)| s.split(", ").map(|s| s.to_string()).collect())
+                .unwrap_or_default();
+            misspelled.insert(word.clone(), suggestions);
+        } else if line.starts_with("# ") {
+            misspelled.insert(word.clone(), vec![]);
+        }
+    }
+
+    misspelled
+}
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use crate::project::ProjectConfig;
+use crate::svc_engine::executable_in;
 use crate::svc_file::ApiResponse;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,69 +20,41 @@ pub struct SyncTexPdfPos {
     pub y: f64,
 }
 
+/// A highlighted region in the PDF. `h`/`v` are the box origin (left, baseline)
+/// and `width`/`height` its size, in PDF points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncTexPdfRect {
+    pub page: i32,
+    pub x: f64,
+    pub y: f64,
+    pub h: f64,
+    pub v: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
 /// Query synctex to find the source location from PDF coordinates
-/// Uses synctex view command: synctex view -i page:x:y:pdffile
+/// Uses synctex edit command: synctex edit -o page:x:y:pdffile
 #[tauri::command]
 pub fn synctex_forward(
     pdf_path: String,
     page: i32,
     x: f64,
     y: f64,
+    project_dir: Option<String>,
 ) -> ApiResponse<SyncTexResult> {
-    // Try to find synctex in common locations
-    let synctex_paths = vec![
-        "synctex",                           // In PATH
-        "/opt/homebrew/bin/synctex",        // Homebrew ARM Mac
-        "/usr/local/bin/synctex",           // Homebrew Intel Mac
-        "/Library/TeX/texbin/synctex",      // MacTeX default
-        "/usr/local/texlive/2025/bin/universal-darwin/synctex",
-        "/usr/local/texlive/2024/bin/universal-darwin/synctex",
-        "/usr/local/texlive/2023/bin/universal-darwin/synctex",
-    ];
-
-    let mut synctex_cmd = None;
-    for path in synctex_paths {
-        if std::path::Path::new(path).exists() || path == "synctex" {
-            synctex_cmd = Some(path);
-            break;
-        }
-    }
-
-    let synctex_bin = match synctex_cmd {
-        Some(cmd) => cmd,
-        None => {
-            // SyncTeX not available - return a helpful error but don't spam console
-            return ApiResponse::error("SyncTeX not installed. Please install MacTeX or TeX Live.".to_string());
-        }
-    };
-
-    // Build synctex command
-    // synctex view -i "page:x:y:pdffile"
+    // synctex edit -o "page:x:y:pdffile"
     let query = format!("{}:{}:{}:{}", page, x, y, pdf_path);
-
-    let output = match Command::new(synctex_bin)
-        .arg("view")
-        .arg("-i")
-        .arg(&query)
-        .output()
-    {
-        Ok(output) => output,
-        Err(e) => {
-            return ApiResponse::error(format!("Failed to run synctex: {}", e));
-        }
+    let stdout = match run_synctex(&["edit", "-o", &query], project_dir.as_deref()) {
+        Ok(stdout) => stdout,
+        Err(e) => return ApiResponse::error(e),
     };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return ApiResponse::error(format!("Synctex command failed: {}", stderr));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
     // Parse synctex output
     // Output format:
     // SyncTeX result begin
-    // Output:path/to/file.tex
+    // Output:path/to/file.pdf
+    // Input:path/to/file.tex
     // Line:123
     // Column:45
     // ...
@@ -88,8 +64,8 @@ pub fn synctex_forward(
     let mut column = 0;
 
     for line_str in stdout.lines() {
-        if line_str.starts_with("Output:") {
-            file = line_str.trim_start_matches("Output:").to_string();
+        if line_str.starts_with("Input:") {
+            file = line_str.trim_start_matches("Input:").to_string();
         } else if line_str.starts_with("Line:") {
             if let Ok(l) = line_str.trim_start_matches("Line:").parse() {
                 line = l;
@@ -116,56 +92,13 @@ pub fn synctex_backward(
     line: i32,
     column: i32,
     pdf_path: String,
+    project_dir: Option<String>,
 ) -> ApiResponse<SyncTexPdfPos> {
-    // Try to find synctex in common locations
-    let synctex_paths = vec![
-        "synctex",                           // In PATH
-        "/opt/homebrew/bin/synctex",        // Homebrew ARM Mac
-        "/usr/local/bin/synctex",           // Homebrew Intel Mac
-        "/Library/TeX/texbin/synctex",      // MacTeX default
-        "/usr/local/texlive/2025/bin/universal-darwin/synctex",
-        "/usr/local/texlive/2024/bin/universal-darwin/synctex",
-        "/usr/local/texlive/2023/bin/universal-darwin/synctex",
-    ];
-
-    let mut synctex_cmd = None;
-    for path in synctex_paths {
-        if std::path::Path::new(path).exists() || path == "synctex" {
-            synctex_cmd = Some(path);
-            break;
-        }
-    }
-
-    let synctex_bin = match synctex_cmd {
-        Some(cmd) => cmd,
-        None => {
-            return ApiResponse::error("SyncTeX not installed. Please install MacTeX or TeX Live.".to_string());
-        }
+    let stdout = match run_synctex_view(&source_path, line, column, &pdf_path, project_dir.as_deref()) {
+        Ok(stdout) => stdout,
+        Err(e) => return ApiResponse::error(e),
     };
 
-    let input = format!("{}:{}:{}", line, column, source_path);
-
-    let output = match Command::new(synctex_bin)
-        .arg("view")
-        .arg("-i")
-        .arg(&input)
-        .arg("-o")
-        .arg(&pdf_path)
-        .output()
-    {
-        Ok(output) => output,
-        Err(e) => {
-            return ApiResponse::error(format!("Failed to run synctex: {}", e));
-        }
-    };
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return ApiResponse::error(format!("Synctex command failed: {}", stderr));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
     // Parse output looking for Page, x, y
     let mut page: i32 = 1;
     let mut x: f64 = 0.0;
@@ -183,3 +116,221 @@ pub fn synctex_backward(
 
     ApiResponse::success(SyncTexPdfPos { page, x, y })
 }
+
+/// Like `synctex_backward`, but returns every box synctex reports for the line
+/// so a multi-line region can be highlighted
+#[tauri::command]
+pub fn synctex_backward_rects(
+    source_path: String,
+    line: i32,
+    column: i32,
+    pdf_path: String,
+    project_dir: Option<String>,
+) -> ApiResponse<Vec<SyncTexPdfRect>> {
+    let stdout = match run_synctex_view(&source_path, line, column, &pdf_path, project_dir.as_deref()) {
+        Ok(stdout) => stdout,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    ApiResponse::success(parse_synctex_rects(&stdout))
+}
+
+// Each result record starts with a Page: line followed by x, y, h, v, W and H
+fn parse_synctex_rects(stdout: &str) -> Vec<SyncTexPdfRect> {
+    let mut rects: Vec<SyncTexPdfRect> = Vec::new();
+
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        if key == "Page" {
+            if let Ok(page) = value.trim().parse() {
+                rects.push(SyncTexPdfRect {
+                    page,
+                    x: 0.0,
+                    y: 0.0,
+                    h: 0.0,
+                    v: 0.0,
+                    width: 0.0,
+                    height: 0.0,
+                });
+            }
+            continue;
+        }
+
+        let (Some(rect), Ok(value)) = (rects.last_mut(), value.trim().parse::<f64>()) else {
+            continue;
+        };
+        match key {
+            "x" => rect.x = value,
+            "y" => rect.y = value,
+            "h" => rect.h = value,
+            "v" => rect.v = value,
+            "W" => rect.width = value,
+            "H" => rect.height = value,
+            _ => {}
+        }
+    }
+
+    rects
+}
+
+// Runs `synctex view` for a source position and returns its stdout
+fn run_synctex_view(
+    source_path: &str,
+    line: i32,
+    column: i32,
+    pdf_path: &str,
+    project_dir: Option<&str>,
+) -> Result<String, String> {
+    let input = format!("{}:{}:{}", line, column, source_path);
+    run_synctex(&["view", "-i", &input, "-o", pdf_path], project_dir)
+}
+
+// Where TeX distributions install synctex; GUI apps on macOS do not inherit the shell's PATH
+const SYNCTEX_FALLBACK_PATHS: &[&str] = &[
+    "/opt/homebrew/bin/synctex",   // Homebrew ARM Mac
+    "/usr/local/bin/synctex",      // Homebrew Intel Mac
+    "/Library/TeX/texbin/synctex", // MacTeX default
+    "/usr/local/texlive/2025/bin/universal-darwin/synctex",
+    "/usr/local/texlive/2024/bin/universal-darwin/synctex",
+    "/usr/local/texlive/2023/bin/universal-darwin/synctex",
+];
+
+/// The synctex of the distribution in `bin_dir`, else the usual lookup
+pub(crate) fn find_synctex(bin_dir: Option<&Path>) -> Option<PathBuf> {
+    locate_synctex(bin_dir, std::env::var_os("PATH").as_deref(), SYNCTEX_FALLBACK_PATHS)
+}
+
+// The distribution's synctex, then the one on `search_path` (a PATH-style list), then the
+// first of `fallbacks` that exists
+fn locate_synctex(bin_dir: Option<&Path>, search_path: Option<&OsStr>, fallbacks: &[&str]) -> Option<PathBuf> {
+    bin_dir
+        .and_then(|dir| executable_in(dir, "synctex"))
+        .or_else(|| {
+            let paths = search_path?;
+            std::env::split_paths(paths).find_map(|dir| executable_in(&dir, "synctex"))
+        })
+        .or_else(|| fallbacks.iter().map(PathBuf::from).find(|path| path.is_file()))
+}
+
+/// Runs the synctex binary with `args` and returns its stdout. With a project, the synctex of
+/// its `tex_dist_path` is preferred.
+pub fn run_synctex(args: &[&str], project_dir: Option<&str>) -> Result<String, String> {
+    let bin_dir = match project_dir {
+        // A distribution missing on this machine just means the usual lookup
+        Some(dir) => ProjectConfig::load(dir)?.tex_bin_dir().ok().flatten(),
+        None => None,
+    };
+    let synctex_bin = find_synctex(bin_dir.as_deref())
+        .ok_or_else(|| "SyncTeX not installed. Please install MacTeX or TeX Live.".to_string())?;
+
+    let output = Command::new(&synctex_bin)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run synctex: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Synctex command failed: {}", stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_synctex_comes_first() {
+        let dist = tempfile::tempdir().unwrap();
+        let path = tempfile::tempdir().unwrap();
+        std::fs::write(dist.path().join("synctex"), "").unwrap();
+        std::fs::write(path.path().join("synctex"), "").unwrap();
+
+        assert_eq!(
+            locate_synctex(Some(dist.path()), Some(path.path().as_os_str()), &[]),
+            Some(dist.path().join("synctex"))
+        );
+    }
+
+    #[test]
+    fn distribution_without_synctex_falls_back_to_path() {
+        let dist = tempfile::tempdir().unwrap();
+        let path = tempfile::tempdir().unwrap();
+        std::fs::write(path.path().join("synctex"), "").unwrap();
+
+        assert_eq!(
+            locate_synctex(Some(dist.path()), Some(path.path().as_os_str()), &[]),
+            Some(path.path().join("synctex"))
+        );
+    }
+
+    #[test]
+    fn fallback_paths_are_tried_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty_path = tempfile::tempdir().unwrap();
+        let (second, third) = (dir.path().join("second"), dir.path().join("third"));
+        std::fs::write(&second, "").unwrap();
+        std::fs::write(&third, "").unwrap();
+        let missing = dir.path().join("missing");
+        let fallbacks = [missing.to_str().unwrap(), second.to_str().unwrap(), third.to_str().unwrap()];
+        let search_path = Some(empty_path.path().as_os_str());
+
+        assert_eq!(locate_synctex(None, search_path, &fallbacks), Some(second.clone()));
+        assert_eq!(locate_synctex(None, search_path, &fallbacks[..1]), None);
+    }
+
+    // A project whose tex_dist_path holds a synctex that records its arguments and answers
+    // like the real one. Returns the project, the distribution and the argument log.
+    #[cfg(unix)]
+    fn project_with_stub_synctex() -> (tempfile::TempDir, tempfile::TempDir, PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let project = tempfile::tempdir().unwrap();
+        let dist = tempfile::tempdir().unwrap();
+        let calls = dist.path().join("calls");
+        let stub = dist.path().join("synctex");
+        std::fs::write(dist.path().join("kpsewhich"), "").unwrap();
+        std::fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh\necho \"$@\" >> '{}'\necho Input:main.tex\necho Line:7\necho Column:2\necho Page:3\necho x:10.5\necho y:20.25\n",
+                calls.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.engine.tex_dist_path = Some(dist.path().to_string_lossy().to_string());
+        config.save(&project.path().to_string_lossy()).unwrap();
+        (project, dist, calls)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forward_search_runs_the_distribution_synctex() {
+        let (project, _dist, calls) = project_with_stub_synctex();
+        let dir = project.path().to_string_lossy().to_string();
+
+        let result = synctex_forward("out/main.pdf".to_string(), 3, 1.5, 2.5, Some(dir));
+        let found = result.data.expect("forward search should succeed");
+        assert_eq!((found.file.as_str(), found.line, found.column), ("main.tex", 7, 2));
+        assert_eq!(std::fs::read_to_string(&calls).unwrap(), "edit -o 3:1.5:2.5:out/main.pdf\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backward_search_runs_the_distribution_synctex() {
+        let (project, _dist, calls) = project_with_stub_synctex();
+        let dir = project.path().to_string_lossy().to_string();
+
+        let result = synctex_backward("main.tex".to_string(), 7, 2, "out/main.pdf".to_string(), Some(dir));
+        let pos = result.data.expect("backward search should succeed");
+        assert_eq!((pos.page, pos.x, pos.y), (3, 10.5, 20.25));
+        assert_eq!(std::fs::read_to_string(&calls).unwrap(), "view -i 7:2:main.tex -o out/main.pdf\n");
+    }
+}
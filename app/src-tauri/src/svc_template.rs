@@ -141,6 +141,62 @@ Conclude your paper.
 \end{document}
 "#;
 
+const THESIS_TEMPLATE: &str = r#"\documentclass[12pt,a4paper]{report}
+\usepackage[utf8]{inputenc}
+\usepackage{amsmath}
+\usepackage{graphicx}
+\usepackage[hidelinks]{hyperref}
+
+\begin{document}
+
+\input{frontmatter}
+
+\tableofcontents
+
+\include{chapters/chapter1}
+\include{chapters/chapter2}
+\include{chapters/chapter3}
+
+\bibliographystyle{plain}
+\bibliography{refs}
+
+\end{document}
+"#;
+
+const THESIS_FRONTMATTER: &str = r#"\begin{titlepage}
+\centering
+{\Huge Thesis Title\par}
+\vspace{2cm}
+{\Large Your Name\par}
+\vspace{1cm}
+A thesis submitted for the degree of\par
+Doctor of Philosophy\par
+\vfill
+Department of Computer Science\par
+University Name\par
+\today
+\end{titlepage}
+
+\begin{abstract}
+Your abstract goes here.
+\end{abstract}
+
+\chapter*{Acknowledgements}
+Thank the people who helped you.
+"#;
+
+const THESIS_CHAPTER1: &str = r#"\chapter{Introduction}
+Write your introduction here.
+"#;
+
+const THESIS_CHAPTER2: &str = r#"\chapter{Background}
+Discuss background and related work.
+"#;
+
+const THESIS_CHAPTER3: &str = r#"\chapter{Conclusion}
+Conclude your thesis.
+"#;
+
 const BIB_TEMPLATE: &str = r#"@article{example2024,
   title={Example Paper Title},
   author={Author, First and Author, Second},
@@ -152,6 +208,32 @@ const BIB_TEMPLATE: &str = r#"@article{example2024,
 }
 "#;
 
+/// Ignore rules written into new projects and merged into existing ones
+pub(crate) const GITIGNORE_TEMPLATE: &str = r#"# Output files
+out/
+*.pdf
+*.aux
+*.log
+*.synctex.gz
+*.fdb_latexmk
+*.fls
+*.toc
+*.bbl
+*.blg
+
+# OS files
+.DS_Store
+Thumbs.db
+
+# Editor files
+*.swp
+*.swo
+*~
+
+# EasyPaper cache
+.easypaper/cache/
+"#;
+
 #[tauri::command]
 pub fn template_list() -> ApiResponse<Vec<Template>> {
     let templates = vec![
@@ -173,11 +255,35 @@ pub fn template_list() -> ApiResponse<Vec<Template>> {
             description: "ACM conference/journal template".to_string(),
             author: Some("ACM".to_string()),
         },
+        Template {
+            id: "thesis".to_string(),
+            name: "Thesis / Report".to_string(),
+            description: "Multi-chapter report template with separate chapter files".to_string(),
+            author: Some("LaTeX".to_string()),
+        },
     ];
 
     ApiResponse::success(templates)
 }
 
+// Files a template creates, relative to the project directory (main.tex first)
+fn template_files(template_id: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    let files = match template_id {
+        "article" => vec![("main.tex", ARTICLE_TEMPLATE)],
+        "ieeetran" => vec![("main.tex", IEEE_TEMPLATE)],
+        "acmart" => vec![("main.tex", ACM_TEMPLATE)],
+        "thesis" => vec![
+            ("main.tex", THESIS_TEMPLATE),
+            ("frontmatter.tex", THESIS_FRONTMATTER),
+            ("chapters/chapter1.tex", THESIS_CHAPTER1),
+            ("chapters/chapter2.tex", THESIS_CHAPTER2),
+            ("chapters/chapter3.tex", THESIS_CHAPTER3),
+        ],
+        _ => return None,
+    };
+    Some(files)
+}
+
 #[tauri::command]
 pub fn template_apply(project_dir: String, template_id: String, project_name: String) -> ApiResponse<()> {
     let project_path = PathBuf::from(&project_dir);
@@ -187,18 +293,23 @@ pub fn template_apply(project_dir: String, template_id: String, project_name: St
         return ApiResponse::error(format!("Failed to create project directory: {}", e));
     }
 
-    // Select template content
-    let main_content = match template_id.as_str() {
-        "article" => ARTICLE_TEMPLATE,
-        "ieeetran" => IEEE_TEMPLATE,
-        "acmart" => ACM_TEMPLATE,
-        _ => return ApiResponse::error(format!("Unknown template: {}", template_id)),
+    // Select template files
+    let files = match template_files(&template_id) {
+        Some(files) => files,
+        None => return ApiResponse::error(format!("Unknown template: {}", template_id)),
     };
 
-    // Write main.tex
-    let main_path = project_path.join("main.tex");
-    if let Err(e) = fs::write(&main_path, main_content) {
-        return ApiResponse::error(format!("Failed to write main.tex: {}", e));
+    // Write main.tex and any additional template files
+    for (rel_path, content) in files {
+        let file_path = project_path.join(rel_path);
+        if let Some(parent) = file_path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return ApiResponse::error(format!("Failed to create directory for {}: {}", rel_path, e));
+            }
+        }
+        if let Err(e) = fs::write(&file_path, content) {
+            return ApiResponse::error(format!("Failed to write {}: {}", rel_path, e));
+        }
     }
 
     // Write refs.bib
@@ -244,32 +355,8 @@ pub fn template_apply(project_dir: String, template_id: String, project_name: St
     }
 
     // Create .gitignore
-    let gitignore_content = r#"# Output files
-out/
-*.pdf
-*.aux
-*.log
-*.synctex.gz
-*.fdb_latexmk
-*.fls
-*.toc
-*.bbl
-*.blg
-
-# OS files
-.DS_Store
-Thumbs.db
-
-# Editor files
-*.swp
-*.swo
-*~
-
-# EasyPaper cache
-.easypaper/cache/
-"#;
     let gitignore_path = project_path.join(".gitignore");
-    if let Err(e) = fs::write(&gitignore_path, gitignore_content) {
+    if let Err(e) = fs::write(&gitignore_path, GITIGNORE_TEMPLATE) {
         return ApiResponse::error(format!("Failed to write .gitignore: {}", e));
     }
 
@@ -282,6 +369,7 @@ pub fn template_get_content(template_id: String) -> ApiResponse<String> {
         "article" => ARTICLE_TEMPLATE,
         "ieeetran" => IEEE_TEMPLATE,
         "acmart" => ACM_TEMPLATE,
+        "thesis" => THESIS_TEMPLATE,
         _ => return ApiResponse::error(format!("Unknown template: {}", template_id)),
     };
 
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core"
-import type { ApiResponse, FileInfo } from "./types"
+import type { ApiResponse, FileListing } from "./types"
 
 export async function fileRead(path: string): Promise<string> {
   const response = await invoke<ApiResponse<string>>("file_read", { path })
@@ -27,8 +27,8 @@ export async function fileWrite(
 export async function fileList(
   dir: string,
   recursive?: boolean
-): Promise<FileInfo[]> {
-  const response = await invoke<ApiResponse<FileInfo[]>>("file_list", {
+): Promise<FileListing> {
+  const response = await invoke<ApiResponse<FileListing>>("file_list", {
     dir,
     recursive,
   })
@@ -66,9 +66,11 @@ export async function fileExists(path: string): Promise<boolean> {
   return response.data ?? false
 }
 
-export async function createDir(path: string): Promise<void> {
-  const response = await invoke<ApiResponse<void>>("create_dir", { path })
-  if (!response.ok) {
+/** Resolves to false when the directory already existed */
+export async function createDir(path: string): Promise<boolean> {
+  const response = await invoke<ApiResponse<boolean>>("create_dir", { path })
+  if (!response.ok || response.data === undefined || response.data === null) {
     throw new Error(response.error || "Failed to create directory")
   }
+  return response.data
 }
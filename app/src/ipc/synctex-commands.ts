@@ -5,13 +5,15 @@ export async function synctexForward(
   pdfPath: string,
   page: number,
   x: number,
-  y: number
+  y: number,
+  projectDir?: string
 ): Promise<SyncTexResult> {
   const response = await invoke<ApiResponse<SyncTexResult>>("synctex_forward", {
     pdfPath,
     page,
     x,
     y,
+    projectDir,
   })
   if (!response.ok || response.data === undefined || response.data === null) {
     throw new Error(response.error || "Failed to query SyncTeX")
@@ -23,13 +25,15 @@ export async function synctexBackward(
   sourcePath: string,
   line: number,
   column: number,
-  pdfPath: string
+  pdfPath: string,
+  projectDir?: string
 ): Promise<SyncTexPdfPos> {
   const response = await invoke<ApiResponse<SyncTexPdfPos>>("synctex_backward", {
     sourcePath,
     line,
     column,
     pdfPath,
+    projectDir,
   })
   if (!response.ok || response.data === undefined || response.data === null) {
     throw new Error(response.error || "Failed to query SyncTeX (backward)")
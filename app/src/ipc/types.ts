@@ -3,6 +3,7 @@ export interface ApiResponse<T> {
   ok: boolean
   data?: T
   error?: string
+  code?: string
 }
 
 // File types
@@ -13,6 +14,16 @@ export interface FileInfo {
   size: number
 }
 
+export interface SkippedEntry {
+  path: string
+  reason: string
+}
+
+export interface FileListing {
+  files: FileInfo[]
+  skipped: SkippedEntry[]
+}
+
 // Build types
 export interface BuildResult {
   success: boolean
@@ -13,7 +13,7 @@ import {
   BellIcon
 } from "lucide-react"
 import { useTheme } from "@/lib/theme-context"
-import { useEditorStore } from "@/store"
+import { useEditorStore, useProjectStore } from "@/store"
 import { synctexForward } from "@/ipc"
 import type { BuildResult } from "@/ipc"
 import "react-pdf/dist/Page/AnnotationLayer.css"
@@ -35,6 +35,7 @@ interface PDFViewerProps {
 export function PDFViewer({ pdfPath, pdfVersion = 0, onCompile, isCompiling, buildResult, onSyncTexClick, jumpToPage }: PDFViewerProps) {
   const { resolvedTheme } = useTheme()
   const { layoutMode, setLayoutMode } = useEditorStore()
+  const { projectDir } = useProjectStore()
   const [numPages, setNumPages] = useState<number>(0)
   const [pageNumber, setPageNumber] = useState<number>(1)
   const [scale, setScale] = useState<number>(1.0)
@@ -223,7 +224,7 @@ export function PDFViewer({ pdfPath, pdfVersion = 0, onCompile, isCompiling, bui
       console.log('PDF clicked:', { page: pageNumber, x, y, pdfPath })
 
       // Query SyncTeX for source location
-      const result = await synctexForward(pdfPath, pageNumber, x, y)
+      const result = await synctexForward(pdfPath, pageNumber, x, y, projectDir ?? undefined)
       console.log('SyncTeX result:', result)
 
       // Call callback to jump to source
@@ -43,7 +43,10 @@ export function FileTree({ onFileSelect }: FileTreeProps) {
   const refreshFiles = async () => {
     if (!projectDir) return
     try {
-      const updatedFiles = await fileList(projectDir, true)
+      const { files: updatedFiles, skipped } = await fileList(projectDir, true)
+      if (skipped.length > 0) {
+        console.warn(`${skipped.length} items couldn't be read:`, skipped)
+      }
       setFiles(updatedFiles)
     } catch (error) {
       console.error("Failed to refresh files:", error)
@@ -146,7 +149,11 @@ export function FileTree({ onFileSelect }: FileTreeProps) {
       const basePath = selectedFolder ? `${projectDir}/${selectedFolder}` : projectDir
       const folderPath = `${basePath}/${folderName}`
 
-      await createDir(folderPath)
+      const created = await createDir(folderPath)
+      if (!created) {
+        alert(`A folder named "${folderName}" already exists`)
+        return
+      }
       await refreshFiles()
 
       // Expand the parent folder if needed
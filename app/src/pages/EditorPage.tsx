@@ -53,8 +53,11 @@ export default function EditorPage() {
 
     const loadFiles = async () => {
       try {
-        const files = await fileList(projectDir, true)
+        const { files, skipped } = await fileList(projectDir, true)
         console.log("Loaded files:", files)
+        if (skipped.length > 0) {
+          console.warn(`${skipped.length} items couldn't be read:`, skipped)
+        }
         setFiles(files)
 
 
@@ -334,7 +337,7 @@ export default function EditorPage() {
   const handleRequestSyncToPdf = async (line: number, column: number) => {
     try {
       if (!activeFile || !pdfPath) return
-      const result = await synctexBackward(activeFile, line, column, pdfPath)
+      const result = await synctexBackward(activeFile, line, column, pdfPath, projectDir ?? undefined)
       if (result && typeof result.page === 'number') {
         setJumpToPdfPage(result.page)
         // Ensure PDF preview is visible